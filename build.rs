@@ -0,0 +1,84 @@
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folders below `data/minecraft` bundled by the `embedded` feature.
+const EMBEDDED_DIRS: [&str; 3] = ["worldgen", "dimension_type", "tags/worldgen"];
+
+/// The data the `embedded` feature bundles, a local checkout of this tag has
+/// to be passed in `VALENCE_WORLDGEN_MCMETA`, nothing is fetched while
+/// building.
+const MCMETA_REPOSITORY: &str = "https://github.com/misode/mcmeta";
+const MCMETA_REF: &str = "1.20.1-data";
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=VALENCE_WORLDGEN_MCMETA");
+
+    if env::var_os("CARGO_FEATURE_EMBEDDED").is_none() {
+        return;
+    }
+
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let Some(mcmeta) = env::var_os("VALENCE_WORLDGEN_MCMETA").map(PathBuf::from) else {
+        panic!(
+            "the embedded feature needs VALENCE_WORLDGEN_MCMETA set to a local checkout of \
+             {MCMETA_REF} of {MCMETA_REPOSITORY}, e.g. `git clone --depth 1 --branch \
+             {MCMETA_REF} {MCMETA_REPOSITORY}`"
+        )
+    };
+    let mcmeta = mcmeta.canonicalize().unwrap_or_else(|e| {
+        panic!(
+            "the embedded feature needs an mcmeta checkout at {}: {e}",
+            mcmeta.display()
+        )
+    });
+    if !mcmeta.join("data/minecraft").is_dir() {
+        panic!(
+            "{} is not an mcmeta checkout of {MCMETA_REF}, data/minecraft is missing",
+            mcmeta.display()
+        );
+    }
+
+    let mut files = vec![];
+    for dir in EMBEDDED_DIRS {
+        let dir = mcmeta.join("data/minecraft").join(dir);
+        println!("cargo:rerun-if-changed={}", dir.display());
+        collect_json(&dir, &mut files);
+    }
+
+    // `PackSource::embedded` binary searches the names as strings, which
+    // order differently than paths do
+    let mut files = files
+        .into_iter()
+        .map(|file| {
+            let name = file.strip_prefix(&mcmeta).unwrap().to_str().unwrap().replace('\\', "/");
+            (name, file)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut code = String::from("pub(crate) static EMBEDDED_FILES: &[(&str, &[u8])] = &[\n");
+    for (name, file) in files {
+        writeln!(code, "    ({name:?}, include_bytes!({:?})),", file.display().to_string()).unwrap();
+    }
+    code.push_str("];\n");
+
+    fs::write(out_dir.join("embedded.rs"), code).unwrap();
+}
+
+fn collect_json(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_json(&path, files);
+        } else if path.extension().is_some_and(|e| e == "json") {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use valence_core::block_pos::BlockPos;
+
+use crate::noise::noise_router::NoiseRouter;
+use crate::registry::holder_set::HolderSet;
+
+pub mod source;
+
+/// A `worldgen/biome`, without mob spawns and client side effects.
+#[derive(Deserialize)]
+pub struct Biome {
+    pub temperature: f32,
+    #[serde(default)]
+    pub temperature_modifier: TemperatureModifier,
+    pub downfall: f32,
+    #[serde(default)]
+    pub carvers: Carvers,
+    /// Placed features by decoration step.
+    #[serde(default)]
+    pub features: Vec<HolderSet>,
+}
+
+/// The configured carvers of a biome, by carving step up to 1.21.1 and a
+/// single set since 1.21.2, which dropped the steps.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum Carvers {
+    ByStep(HashMap<String, HolderSet>),
+    All(HolderSet),
+}
+
+impl Carvers {
+    /// Every set of carvers, regardless of the step.
+    pub fn sets(&self) -> Vec<&HolderSet> {
+        match self {
+            Carvers::ByStep(steps) => steps.values().collect(),
+            Carvers::All(carvers) => vec![carvers],
+        }
+    }
+}
+
+impl Default for Carvers {
+    fn default() -> Self {
+        Carvers::ByStep(HashMap::new())
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum TemperatureModifier {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "frozen")]
+    Frozen,
+}
+
+#[derive(Deserialize, Copy, Clone)]
+pub struct ClimatePoint {
+    temperature: ClimateRange,
+    humidity: ClimateRange,
+    continentalness: ClimateRange,
+    erosion: ClimateRange,
+    depth: ClimateRange,
+    weirdness: ClimateRange,
+    offset: f64,
+}
+
+impl ClimatePoint {
+    /// Squared distance to `target` in quantized climate space, lower is
+    /// better.
+    pub fn fitness(&self, target: &TargetPoint) -> i64 {
+        square(self.temperature.distance(target.temperature))
+            + square(self.humidity.distance(target.humidity))
+            + square(self.continentalness.distance(target.continentalness))
+            + square(self.erosion.distance(target.erosion))
+            + square(self.depth.distance(target.depth))
+            + square(self.weirdness.distance(target.weirdness))
+            + square(quantize(self.offset as f32))
+    }
+}
+
+#[derive(Deserialize, Copy, Clone)]
+#[serde(untagged)]
+pub enum ClimateRange {
+    Point(f64),
+    Range([f64; 2]),
+}
+
+impl ClimateRange {
+    fn quantized(&self) -> (i64, i64) {
+        match self {
+            ClimateRange::Point(v) => (quantize(*v as f32), quantize(*v as f32)),
+            ClimateRange::Range([min, max]) => (quantize(*min as f32), quantize(*max as f32)),
+        }
+    }
+
+    fn distance(&self, v: i64) -> i64 {
+        let (min, max) = self.quantized();
+        let above = v - max;
+        let below = min - v;
+        if above > 0 {
+            above
+        } else {
+            below.max(0)
+        }
+    }
+}
+
+/// A sampled climate, quantized like vanilla's `Climate.TargetPoint`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TargetPoint {
+    pub temperature: i64,
+    pub humidity: i64,
+    pub continentalness: i64,
+    pub erosion: i64,
+    pub depth: i64,
+    pub weirdness: i64,
+}
+
+impl NoiseRouter {
+    /// Samples the climate at the given quart position.
+    pub fn sample_climate(&self, quart_x: i32, quart_y: i32, quart_z: i32) -> TargetPoint {
+        let pos = BlockPos::new(quart_x << 2, quart_y << 2, quart_z << 2);
+
+        TargetPoint {
+            temperature: quantize(self.temperature.compute(pos) as f32),
+            humidity: quantize(self.vegetation.compute(pos) as f32),
+            continentalness: quantize(self.continents.compute(pos) as f32),
+            erosion: quantize(self.erosion.compute(pos) as f32),
+            depth: quantize(self.depth.compute(pos) as f32),
+            weirdness: quantize(self.ridges.compute(pos) as f32),
+        }
+    }
+}
+
+pub(crate) fn quantize(v: f32) -> i64 {
+    (v * 10000.0) as i64
+}
+
+fn square(v: i64) -> i64 {
+    v * v
+}
+
+/// Finds the position whose climate fits `targets` best, see vanilla's
+/// `Climate.findSpawnPosition`. The y coordinate is always `0`.
+pub fn find_spawn_position(targets: &[ClimatePoint], router: &NoiseRouter) -> BlockPos {
+    let mut best = spawn_position_and_fitness(targets, router, 0, 0);
+    best = radial_search(targets, router, best, 2048.0, 512.0);
+    best = radial_search(targets, router, best, 512.0, 32.0);
+    best.0
+}
+
+fn radial_search(
+    targets: &[ClimatePoint],
+    router: &NoiseRouter,
+    mut best: (BlockPos, i64),
+    max_radius: f32,
+    step: f32,
+) -> (BlockPos, i64) {
+    let origin = best.0;
+    let mut angle = 0.0_f32;
+    let mut radius = step;
+
+    while radius <= max_radius {
+        let x = origin.x + ((angle as f64).sin() * radius as f64) as i32;
+        let z = origin.z + ((angle as f64).cos() * radius as f64) as i32;
+
+        let candidate = spawn_position_and_fitness(targets, router, x, z);
+        if candidate.1 < best.1 {
+            best = candidate;
+        }
+
+        angle += step / radius;
+        if angle as f64 > std::f64::consts::PI * 2.0 {
+            angle = 0.0;
+            radius += step;
+        }
+    }
+
+    best
+}
+
+fn spawn_position_and_fitness(
+    targets: &[ClimatePoint],
+    router: &NoiseRouter,
+    x: i32,
+    z: i32,
+) -> (BlockPos, i64) {
+    let distance_squared = (square(x as i64) + square(z as i64)) as f64;
+    let distance_penalty = ((10000.0_f32 * 10000.0_f32) as f64
+        * (distance_squared / (2500.0 * 2500.0)).powi(2)) as i64;
+
+    let climate = TargetPoint {
+        depth: 0,
+        ..router.sample_climate(x >> 2, 0, z >> 2)
+    };
+    let fitness = targets
+        .iter()
+        .map(|p| p.fitness(&climate))
+        .min()
+        .unwrap_or(i64::MAX);
+
+    (
+        BlockPos::new(x, 0, z),
+        distance_penalty.saturating_add(fitness),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::biome::{Biome, Carvers, ClimatePoint, TargetPoint};
+    use crate::registry::holder_set::HolderSet;
+
+    #[test]
+    fn climate_point_fitness_test() {
+        let point: ClimatePoint = serde_json::from_str(
+            r#"{
+              "continentalness": [-0.11, 1.0],
+              "depth": 0.0,
+              "erosion": [-1.0, 1.0],
+              "humidity": [-1.0, 1.0],
+              "offset": 0.0,
+              "temperature": [-1.0, 1.0],
+              "weirdness": [-1.0, -0.16]
+            }"#,
+        )
+        .expect("should be a valid climate point");
+
+        let inside = TargetPoint {
+            temperature: 0,
+            humidity: 0,
+            continentalness: 0,
+            erosion: 0,
+            depth: 0,
+            weirdness: -5000,
+        };
+        assert_eq!(0, point.fitness(&inside));
+
+        let outside = TargetPoint {
+            continentalness: -2100,
+            weirdness: 0,
+            ..inside
+        };
+        assert_eq!(1000 * 1000 + 1600 * 1600, point.fitness(&outside));
+    }
+
+    #[test]
+    fn biome_carvers_test() {
+        let biome = |carvers: &str| -> Biome {
+            serde_json::from_str(&format!(
+                r#"{{"temperature": 0.8, "downfall": 0.4, "carvers": {carvers}}}"#
+            ))
+            .expect("should be a valid biome")
+        };
+
+        // 1.20.1
+        match biome(r##"{"air": ["minecraft:cave", "minecraft:canyon"], "liquid": "#test:c"}"##)
+            .carvers
+        {
+            Carvers::ByStep(steps) => assert_eq!(2, steps.len()),
+            Carvers::All(_) => panic!("carvers should be by step"),
+        }
+        // 1.21.2 and later
+        let carvers = biome(r#"["minecraft:cave", "minecraft:canyon"]"#).carvers;
+        match carvers.sets()[..] {
+            [HolderSet::Direct(ref ids)] => assert_eq!(2, ids.len()),
+            _ => panic!("carvers should be a single set"),
+        }
+        assert!(matches!(
+            biome(r##""#minecraft:overworld""##).carvers,
+            Carvers::All(HolderSet::Tag(_))
+        ));
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+
+use eyre::bail;
+use serde::Deserialize;
+use valence_core::block_pos::BlockPos;
+use valence_core::ident;
+use valence_core::ident::Ident;
+
+use crate::biome::ClimatePoint;
+use crate::noise::noise_router::NoiseRouter;
+
+/// Chooses the biome at a quart position, the `biome_source` of a dimension's
+/// generator.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum BiomeSource {
+    #[serde(rename = "minecraft:fixed")]
+    Fixed { biome: Ident<String> },
+    /// Vanilla's presets are built into the game and aren't supported, a
+    /// source naming only a `preset` has no biomes and fails [`check`].
+    ///
+    /// [`check`]: BiomeSource::check
+    #[serde(rename = "minecraft:multi_noise")]
+    MultiNoise {
+        #[serde(default)]
+        biomes: Vec<BiomeParameters>,
+        preset: Option<Ident<String>>,
+    },
+    #[serde(rename = "minecraft:the_end")]
+    TheEnd {},
+}
+
+/// A `worldgen/multi_noise_biome_source_parameter_list`, naming one of the
+/// presets built into the game.
+#[derive(Deserialize)]
+pub struct MultiNoiseBiomeSourceParameterList {
+    pub preset: Ident<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct BiomeParameters {
+    pub biome: Ident<String>,
+    pub parameters: ClimatePoint,
+}
+
+impl BiomeSource {
+    /// Fails for sources that can't choose a biome, like a `multi_noise`
+    /// source naming only one of vanilla's built in presets.
+    pub fn check(&self) -> eyre::Result<()> {
+        match self {
+            BiomeSource::MultiNoise { biomes, preset } if biomes.is_empty() => match preset {
+                Some(preset) => bail!("unsupported multi_noise biome source preset {preset}"),
+                None => bail!("multi_noise biome source without biomes"),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Panics for sources that fail [`check`](BiomeSource::check).
+    pub fn biome(
+        &self,
+        quart_x: i32,
+        quart_y: i32,
+        quart_z: i32,
+        router: &NoiseRouter,
+    ) -> Ident<&str> {
+        match self {
+            BiomeSource::Fixed { biome } => biome.as_str_ident(),
+            BiomeSource::MultiNoise { biomes, .. } => {
+                let target = router.sample_climate(quart_x, quart_y, quart_z);
+
+                // vanilla walks an R-tree, the closest entry is the same
+                biomes
+                    .iter()
+                    .min_by_key(|b| b.parameters.fitness(&target))
+                    .map(|b| b.biome.as_str_ident())
+                    .expect("multi_noise biome source without biomes")
+            }
+            BiomeSource::TheEnd {} => {
+                let (chunk_x, chunk_z) = (quart_x >> 2, quart_z >> 2);
+                if chunk_x as i64 * chunk_x as i64 + chunk_z as i64 * chunk_z as i64 <= 4096 {
+                    return ident!("minecraft:the_end");
+                }
+
+                let erosion = router.erosion.compute(BlockPos::new(
+                    (chunk_x * 2 + 1) * 8,
+                    quart_y << 2,
+                    (chunk_z * 2 + 1) * 8,
+                ));
+                if erosion > 0.25 {
+                    ident!("minecraft:end_highlands")
+                } else if erosion >= -0.0625 {
+                    ident!("minecraft:end_midlands")
+                } else if erosion < -0.21875 {
+                    ident!("minecraft:small_end_islands")
+                } else {
+                    ident!("minecraft:end_barrens")
+                }
+            }
+        }
+    }
+
+    /// Every biome this source can ever return.
+    pub fn possible_biomes(&self) -> HashSet<Ident<&str>> {
+        match self {
+            BiomeSource::Fixed { biome } => HashSet::from([biome.as_str_ident()]),
+            BiomeSource::MultiNoise { biomes, .. } => {
+                biomes.iter().map(|b| b.biome.as_str_ident()).collect()
+            }
+            BiomeSource::TheEnd {} => HashSet::from([
+                ident!("minecraft:the_end"),
+                ident!("minecraft:end_highlands"),
+                ident!("minecraft:end_midlands"),
+                ident!("minecraft:small_end_islands"),
+                ident!("minecraft:end_barrens"),
+            ]),
+        }
+    }
+}
+
+/// The horizontal offsets vanilla's `BlockPos.spiralAround` visits, walking
+/// east first and then south in growing squares of up to `radius`.
+pub(crate) fn spiral_around(radius: i32) -> impl Iterator<Item = (i32, i32)> {
+    const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+    let legs = 4 * radius;
+    let (mut x, mut z) = (0, 1);
+    let (mut leg, mut leg_size, mut leg_index) = (-1, 0, 0);
+
+    std::iter::from_fn(move || {
+        let (dx, dz) = DIRECTIONS[((leg + 4) % 4) as usize];
+        (x, z) = (x + dx, z + dz);
+
+        if leg_index >= leg_size {
+            if leg >= legs {
+                return None;
+            }
+            leg += 1;
+            leg_index = 0;
+            leg_size = leg / 2 + 1;
+        }
+        leg_index += 1;
+
+        Some((x, z))
+    })
+}
+
+/// Values between `min` and `max` in steps of `step`, alternating above and
+/// below `origin`, see vanilla's `Mth.outFromOrigin`.
+pub(crate) fn out_from_origin(origin: i32, min: i32, max: i32, step: i32) -> Vec<i32> {
+    if origin < min || origin > max || step < 1 {
+        return vec![];
+    }
+
+    let mut values = vec![];
+    let mut v = origin;
+    loop {
+        let distance = (origin - v).abs();
+        if origin - distance < min && origin + distance > max {
+            return values;
+        }
+        values.push(v);
+
+        let below = v <= origin;
+        if !below || origin + distance + step > max {
+            let next = origin - distance - if below { step } else { 0 };
+            if next >= min {
+                v = next;
+                continue;
+            }
+        }
+        v = origin + distance + step;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::biome::source::{out_from_origin, spiral_around, BiomeSource};
+
+    #[test]
+    fn spiral_around_test() {
+        let spiral = spiral_around(1).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (0, 0),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ],
+            spiral
+        );
+
+        assert_eq!(25, spiral_around(2).count());
+        assert_eq!(vec![(0, 0)], spiral_around(0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn out_from_origin_test() {
+        assert_eq!(vec![5, 7, 3, 9, 1, 11], out_from_origin(5, 0, 11, 2));
+        assert_eq!(vec![0, 1, 2, 3], out_from_origin(0, 0, 3, 1));
+        assert_eq!(vec![3, 2, 1, 0], out_from_origin(3, 0, 3, 1));
+        assert!(out_from_origin(12, 0, 11, 2).is_empty());
+    }
+
+    #[test]
+    fn preset_check_test() {
+        let source: BiomeSource = serde_json::from_str(
+            r#"{"type": "minecraft:multi_noise", "preset": "minecraft:overworld"}"#,
+        )
+        .expect("should be a valid biome source");
+        let err = source.check().expect_err("presets aren't built in");
+        assert_eq!(
+            "unsupported multi_noise biome source preset minecraft:overworld",
+            err.to_string()
+        );
+
+        let source: BiomeSource =
+            serde_json::from_str(r#"{"type": "minecraft:fixed", "biome": "minecraft:plains"}"#)
+                .expect("should be a valid biome source");
+        assert!(source.check().is_ok());
+    }
+}
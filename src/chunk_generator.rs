@@ -0,0 +1,681 @@
+use std::collections::{HashMap, HashSet};
+use std::f64::consts::PI;
+use std::iter::zip;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use eyre::eyre;
+use valence_block::BlockState;
+use valence_core::block_pos::BlockPos;
+use valence_core::ident::Ident;
+
+use crate::biome::find_spawn_position;
+use crate::biome::source::{out_from_origin, spiral_around, BiomeSource};
+use crate::density_function::beardifier::Beardifier;
+use crate::density_function::PositionsContextProvider;
+use crate::heightmap::Heightmap;
+use crate::noise::deserialize::NoiseGeneratorSettings;
+use crate::noise::noise_router::NoiseRouter;
+use crate::random::legacy::LegacyRandom;
+use crate::random::random_state::RandomState;
+use crate::random::RandomSource;
+use crate::registry::{Registry, BIOME};
+use crate::structure::placement::{PlacementKind, StructureSet};
+use crate::structure::{JigsawJunction, RigidPiece, Structure};
+
+/// Vanilla's global lava level, fluids below it are lava.
+const LAVA_LEVEL: i32 = -54;
+
+/// Radius in chunks around the climate spawn that is searched for a safe
+/// surface.
+const SPAWN_SEARCH_RADIUS: i32 = 5;
+
+/// Density above which `initial_density_without_jaggedness` is considered
+/// solid when estimating the surface.
+const PRELIMINARY_SURFACE_THRESHOLD: f64 = 0.390625;
+
+/// Radius in blocks around a concentric ring position searched for a
+/// preferred biome.
+const RING_BIOME_SEARCH_RADIUS: i32 = 112;
+
+type RingPositions = Arc<Vec<(i32, i32)>>;
+
+pub struct ChunkGenerator {
+    settings: Arc<NoiseGeneratorSettings>,
+    biome_source: BiomeSource,
+    random_state: Arc<RandomState>,
+    router: NoiseRouter,
+    ring_positions: RwLock<HashMap<Ident<String>, RingPositions>>,
+}
+
+impl ChunkGenerator {
+    pub fn new(
+        settings: Arc<NoiseGeneratorSettings>,
+        biome_source: BiomeSource,
+        random_state: Arc<RandomState>,
+    ) -> eyre::Result<Self> {
+        biome_source.check()?;
+        let router = settings.noise_router.compile(&random_state)?;
+
+        Ok(Self {
+            settings,
+            biome_source,
+            random_state,
+            router,
+            ring_positions: RwLock::default(),
+        })
+    }
+
+    pub fn settings(&self) -> &NoiseGeneratorSettings {
+        &self.settings
+    }
+
+    pub fn biome_source(&self) -> &BiomeSource {
+        &self.biome_source
+    }
+
+    pub fn random_state(&self) -> &RandomState {
+        &self.random_state
+    }
+
+    pub fn router(&self) -> &NoiseRouter {
+        &self.router
+    }
+
+    pub fn min_y(&self) -> i32 {
+        self.settings.noise_settings.min_y
+    }
+
+    pub fn max_y(&self) -> i32 {
+        self.min_y() + self.settings.noise_settings.height as i32
+    }
+
+    /// Estimates the surface height at quart resolution by scanning
+    /// `initial_density_without_jaggedness` downwards one cell at a time.
+    ///
+    /// Returns `i32::MAX` if no solid cell is found, just like vanilla.
+    pub fn preliminary_surface_level(&self, x: i32, z: i32) -> i32 {
+        let (x, z) = ((x >> 2) << 2, (z >> 2) << 2);
+        let cell_height = self.settings.noise_settings.cell_height() as usize;
+
+        (self.min_y()..=self.max_y())
+            .rev()
+            .step_by(cell_height)
+            .find(|&y| {
+                self.router
+                    .initial_density_without_jaggedness
+                    .compute(BlockPos::new(x, y, z))
+                    > PRELIMINARY_SURFACE_THRESHOLD
+            })
+            .unwrap_or(i32::MAX)
+    }
+
+    /// The y level above the highest block matching `heightmap` in the noise
+    /// generated column, or the bottom of the world if there is none.
+    ///
+    /// Only the single column is evaluated, surfaces, features and
+    /// structures are not taken into account.
+    pub fn base_height(&self, x: i32, z: i32, heightmap: Heightmap) -> i32 {
+        let column = self.base_column(x, z);
+        match column.iter().rposition(|&state| heightmap.is_opaque(state)) {
+            Some(top) => self.min_y() + top as i32 + 1,
+            None => self.min_y(),
+        }
+    }
+
+    /// The noise generated block states of a column, starting at the bottom
+    /// of the world.
+    pub fn base_column(&self, x: i32, z: i32) -> Vec<BlockState> {
+        self.base_column_with(&self.router, x, z)
+    }
+
+    /// Like [`base_column`](Self::base_column), sampling `router`, e.g. the
+    /// [`chunk_router`](Self::chunk_router) of the chunk containing the
+    /// column.
+    pub fn base_column_with(&self, router: &NoiseRouter, x: i32, z: i32) -> Vec<BlockState> {
+        let positions = (self.min_y()..self.max_y())
+            .map(|y| BlockPos::new(x, y, z))
+            .collect::<Vec<_>>();
+        let mut densities = vec![0.0; positions.len()];
+        router
+            .final_density
+            .fill(&mut densities, &PositionsContextProvider::new(&positions));
+
+        zip(positions, densities)
+            .map(|(pos, density)| self.noise_block_state(pos.y, density))
+            .collect()
+    }
+
+    /// The router used to generate the chunk at the given chunk coordinates,
+    /// with `minecraft:beardifier` adapting the terrain to the structure
+    /// `pieces` and `junctions` nearby, like vanilla's `NoiseChunk`.
+    pub fn chunk_router(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        pieces: impl IntoIterator<Item = RigidPiece>,
+        junctions: impl IntoIterator<Item = JigsawJunction>,
+    ) -> eyre::Result<NoiseRouter> {
+        let beardifier = Beardifier::for_chunk(chunk_x, chunk_z, pieces, junctions);
+        self.settings
+            .noise_router
+            .compile_for_chunk(&self.random_state, beardifier)
+    }
+
+    /// Picks the world spawn like vanilla does for new worlds: the position
+    /// fitting `spawn_target` best, moved onto the first safe surface found
+    /// spiraling outwards from its chunk.
+    pub fn spawn_position(&self) -> BlockPos {
+        let climate_spawn = find_spawn_position(&self.settings.spawn_target, &self.router);
+        let (chunk_x, chunk_z) = (climate_spawn.x >> 4, climate_spawn.z >> 4);
+
+        let (mut x, mut z) = (0, 0);
+        let (mut dx, mut dz) = (0, -1);
+        let diameter = SPAWN_SEARCH_RADIUS * 2 + 1;
+        for _ in 0..diameter * diameter {
+            if let Some(pos) = self.safe_spawn_in_chunk(chunk_x + x, chunk_z + z) {
+                return pos;
+            }
+
+            if x == z || (x < 0 && x == -z) || (x > 0 && x == 1 - z) {
+                (dx, dz) = (-dz, dx);
+            }
+            (x, z) = (x + dx, z + dz);
+        }
+
+        let (x, z) = ((chunk_x << 4) + 8, (chunk_z << 4) + 8);
+        BlockPos::new(x, self.base_height(x, z, Heightmap::WorldSurfaceWg), z)
+    }
+
+    /// The biome at the given quart position.
+    pub fn biome(&self, quart_x: i32, quart_y: i32, quart_z: i32) -> Ident<&str> {
+        self.biome_source
+            .biome(quart_x, quart_y, quart_z, &self.router)
+    }
+
+    /// Finds a biome matching `predicate` like vanilla's `/locate biome`,
+    /// spiraling outwards from `origin` up to `radius` blocks. Columns are
+    /// `horizontal_step` blocks apart and are checked every `vertical_step`
+    /// blocks, starting at the height of `origin`.
+    pub fn locate_biome(
+        &self,
+        origin: BlockPos,
+        radius: i32,
+        horizontal_step: i32,
+        vertical_step: i32,
+        predicate: impl Fn(&Ident<&str>) -> bool,
+    ) -> Option<(BlockPos, Ident<&str>)> {
+        let wanted = self
+            .biome_source
+            .possible_biomes()
+            .into_iter()
+            .filter(|b| predicate(b))
+            .collect::<HashSet<_>>();
+        if wanted.is_empty() || horizontal_step < 1 {
+            return None;
+        }
+
+        let ys = out_from_origin(origin.y, self.min_y() + 1, self.max_y(), vertical_step);
+        spiral_around(radius.div_euclid(horizontal_step)).find_map(|(dx, dz)| {
+            let x = origin.x + dx * horizontal_step;
+            let z = origin.z + dz * horizontal_step;
+
+            ys.iter().find_map(|&y| {
+                let biome = self.biome(x >> 2, y >> 2, z >> 2);
+                wanted
+                    .contains(&biome)
+                    .then(|| (BlockPos::new(x, y, z), biome))
+            })
+        })
+    }
+
+    /// Finds the structure start matching `predicate` closest to `origin`
+    /// like vanilla's `/locate structure`, walking the placement grids of
+    /// `structure_sets` outwards for up to `search_radius` grid cells. Every
+    /// position of sets placed in concentric rings is checked.
+    ///
+    /// No chunks are generated, a structure is assumed to start wherever its
+    /// placement allows it and the biome at the surface of the chunk center
+    /// is valid.
+    pub fn locate_structure(
+        &self,
+        structure_sets: &[Ident<&str>],
+        origin: BlockPos,
+        search_radius: i32,
+        predicate: impl Fn(&Ident<&str>) -> bool,
+    ) -> eyre::Result<Option<(BlockPos, Ident<String>)>> {
+        let sets = structure_sets
+            .iter()
+            .map(|id| Ok((id, self.random_state.registry.structure_set(id)?)))
+            .collect::<eyre::Result<Vec<_>>>()?;
+
+        let mut nearest = None;
+        for (id, set) in &sets {
+            if let PlacementKind::ConcentricRings { .. } = set.placement.kind {
+                if let Some(found) = self.structure_on_rings(id, set, origin, &predicate)? {
+                    nearest = Some(nearer(origin, nearest, found));
+                }
+            }
+        }
+
+        let (chunk_x, chunk_z) = (origin.x >> 4, origin.z >> 4);
+        for ring in 0..=search_radius {
+            let mut found_any = false;
+
+            for (id, set) in &sets {
+                if let Some(found) =
+                    self.structure_in_ring(id, set, chunk_x, chunk_z, ring, &predicate)?
+                {
+                    found_any = true;
+                    nearest = Some(nearer(origin, nearest, found));
+                }
+            }
+
+            if found_any {
+                return Ok(nearest);
+            }
+        }
+
+        Ok(nearest)
+    }
+
+    /// Whether a structure of `structure_set` may start in the chunk at the
+    /// given chunk coordinates, taking frequency reduction and the exclusion
+    /// zone of its placement into account.
+    ///
+    /// Sets excluding themselves, directly or through other sets, are an
+    /// error.
+    pub fn is_structure_chunk(
+        &self,
+        structure_set: &Ident<&str>,
+        chunk_x: i32,
+        chunk_z: i32,
+    ) -> eyre::Result<bool> {
+        self.is_structure_chunk_excluding(structure_set, chunk_x, chunk_z, &mut vec![])
+    }
+
+    /// [`is_structure_chunk`](Self::is_structure_chunk), `excluding` are the
+    /// sets whose exclusion zones led to `structure_set`.
+    fn is_structure_chunk_excluding(
+        &self,
+        structure_set: &Ident<&str>,
+        chunk_x: i32,
+        chunk_z: i32,
+        excluding: &mut Vec<String>,
+    ) -> eyre::Result<bool> {
+        if excluding.iter().any(|id| id == structure_set.as_str()) {
+            return Err(eyre!(
+                "structure set exclusion zone cycle: {} -> {structure_set}",
+                excluding.join(" -> ")
+            ));
+        }
+
+        let set = self.random_state.registry.structure_set(structure_set)?;
+        let placement = &set.placement;
+        let seed = self.random_state.seed;
+
+        let placed = match placement.kind {
+            PlacementKind::RandomSpread { .. } => {
+                placement.is_placement_chunk(seed, chunk_x, chunk_z)?
+            }
+            PlacementKind::ConcentricRings { .. } => self
+                .ring_positions(structure_set)?
+                .contains(&(chunk_x, chunk_z)),
+        };
+        if !placed || !placement.should_generate(seed, chunk_x, chunk_z) {
+            return Ok(false);
+        }
+
+        let Some(zone) = &placement.exclusion_zone else {
+            return Ok(true);
+        };
+        let other_set = zone.other_set.as_str_ident();
+        excluding.push(structure_set.to_string());
+        for x in chunk_x - zone.chunk_count..=chunk_x + zone.chunk_count {
+            for z in chunk_z - zone.chunk_count..=chunk_z + zone.chunk_count {
+                if self.is_structure_chunk_excluding(&other_set, x, z, excluding)? {
+                    excluding.pop();
+                    return Ok(false);
+                }
+            }
+        }
+        excluding.pop();
+
+        Ok(true)
+    }
+
+    /// The chunks structures of a `structure_set` placed in concentric rings
+    /// may start in, like vanilla's `ChunkGeneratorStructureState`. Each
+    /// position is moved to a random preferred biome nearby if there is one.
+    pub fn ring_positions(&self, structure_set: &Ident<&str>) -> eyre::Result<RingPositions> {
+        if let Some(positions) = self
+            .ring_positions
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&structure_set.to_string_ident())
+        {
+            return Ok(positions.clone());
+        }
+
+        let set = self.random_state.registry.structure_set(structure_set)?;
+        let PlacementKind::ConcentricRings {
+            distance,
+            mut spread,
+            count,
+            ref preferred_biomes,
+        } = set.placement.kind
+        else {
+            return Err(eyre!("{structure_set} is not placed in concentric rings"));
+        };
+
+        let preferred = self
+            .biome_source
+            .possible_biomes()
+            .into_iter()
+            .filter_map(|b| {
+                preferred_biomes
+                    .contains(self.random_state.registry.as_ref(), BIOME, &b)
+                    .map(|contains| contains.then_some(b))
+                    .transpose()
+            })
+            .collect::<eyre::Result<HashSet<_>>>()?;
+
+        let mut r = LegacyRandom::new(self.random_state.seed);
+        let mut angle = r.next_f64() * PI * 2.0;
+        let (mut placed_in_ring, mut ring) = (0, 0);
+        let mut positions = Vec::with_capacity(count.max(0) as usize);
+        for i in 0..count {
+            let distance = (4 * distance + distance * ring * 6) as f64
+                + (r.next_f64() - 0.5) * (distance as f64 * 2.5);
+            // Java's `Math.round`
+            let chunk_x = (angle.cos() * distance + 0.5).floor() as i32;
+            let chunk_z = (angle.sin() * distance + 0.5).floor() as i32;
+            let mut biome_random = r.fork();
+            positions.push(
+                self.find_ring_biome(chunk_x, chunk_z, &preferred, biome_random.as_mut())
+                    .unwrap_or((chunk_x, chunk_z)),
+            );
+
+            angle += PI * 2.0 / spread as f64;
+            placed_in_ring += 1;
+            if placed_in_ring == spread {
+                placed_in_ring = 0;
+                ring += 1;
+                spread += 2 * spread / (ring + 1);
+                spread = spread.min(count - i);
+                angle += r.next_f64() * PI * 2.0;
+            }
+        }
+
+        Ok(self
+            .ring_positions
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(structure_set.to_string_ident())
+            .or_insert_with(|| Arc::new(positions))
+            .clone())
+    }
+
+    /// Vanilla's `BiomeSource.findBiomeHorizontal` at y 0 around the center
+    /// of a chunk, picking one of the matching quarts at random.
+    fn find_ring_biome(
+        &self,
+        chunk_x: i32,
+        chunk_z: i32,
+        preferred: &HashSet<Ident<&str>>,
+        r: &mut dyn RandomSource,
+    ) -> Option<(i32, i32)> {
+        let (quart_x, quart_z) = (((chunk_x << 4) + 8) >> 2, ((chunk_z << 4) + 8) >> 2);
+        let radius = RING_BIOME_SEARCH_RADIUS >> 2;
+
+        let mut found = None;
+        let mut matches = 0;
+        for dz in -radius..=radius {
+            for dx in -radius..=radius {
+                let (x, z) = (quart_x + dx, quart_z + dz);
+                if !preferred.contains(&self.biome(x, 0, z)) {
+                    continue;
+                }
+
+                if found.is_none() || r.next_i32_bound(matches + 1) == 0 {
+                    found = Some((x >> 2, z >> 2));
+                }
+                matches += 1;
+            }
+        }
+
+        found
+    }
+
+    /// The nearest start of `set`, which is placed in concentric rings.
+    fn structure_on_rings(
+        &self,
+        id: &Ident<&str>,
+        set: &StructureSet,
+        origin: BlockPos,
+        predicate: &impl Fn(&Ident<&str>) -> bool,
+    ) -> eyre::Result<Option<(BlockPos, Ident<String>)>> {
+        let mut nearest: Option<(i64, BlockPos, Ident<String>)> = None;
+
+        for &(x, z) in self.ring_positions(id)?.iter() {
+            // vanilla compares the chunk centers at y 32
+            let center = BlockPos::new((x << 4) + 8, 32, (z << 4) + 8);
+            let distance = distance_squared(origin, center);
+            if nearest.as_ref().is_some_and(|(d, ..)| *d <= distance) {
+                continue;
+            }
+
+            if let Some(structure) = self.structure_starting_at(id, set, x, z, predicate)? {
+                nearest = Some((distance, set.placement.locate_pos(x, z), structure));
+            }
+        }
+
+        Ok(nearest.map(|(_, pos, structure)| (pos, structure)))
+    }
+
+    fn structure_in_ring(
+        &self,
+        id: &Ident<&str>,
+        set: &StructureSet,
+        chunk_x: i32,
+        chunk_z: i32,
+        ring: i32,
+        predicate: &impl Fn(&Ident<&str>) -> bool,
+    ) -> eyre::Result<Option<(BlockPos, Ident<String>)>> {
+        // concentric rings are searched by `structure_on_rings`
+        let PlacementKind::RandomSpread { spacing, .. } = set.placement.kind else {
+            return Ok(None);
+        };
+
+        let seed = self.random_state.seed;
+        for i in -ring..=ring {
+            for j in -ring..=ring {
+                if i.abs() != ring && j.abs() != ring {
+                    continue;
+                }
+
+                let (x, z) = set.placement.potential_structure_chunk(
+                    seed,
+                    chunk_x + spacing * i,
+                    chunk_z + spacing * j,
+                )?;
+                if let Some(structure) = self.structure_starting_at(id, set, x, z, predicate)? {
+                    return Ok(Some((set.placement.locate_pos(x, z), structure)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The first structure of `set` matching `predicate` that may start in
+    /// chunk `x`, `z`.
+    fn structure_starting_at(
+        &self,
+        id: &Ident<&str>,
+        set: &StructureSet,
+        x: i32,
+        z: i32,
+        predicate: &impl Fn(&Ident<&str>) -> bool,
+    ) -> eyre::Result<Option<Ident<String>>> {
+        if !self.is_structure_chunk(id, x, z)? {
+            return Ok(None);
+        }
+
+        for entry in &set.structures {
+            let structure_id = entry.structure.as_str_ident();
+            if !predicate(&structure_id) {
+                continue;
+            }
+
+            let structure = self.random_state.registry.structure(&structure_id)?;
+            if self.is_valid_structure_start(&structure, x, z)? {
+                return Ok(Some(entry.structure.clone()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn is_valid_structure_start(
+        &self,
+        structure: &Structure,
+        chunk_x: i32,
+        chunk_z: i32,
+    ) -> eyre::Result<bool> {
+        let (x, z) = ((chunk_x << 4) + 8, (chunk_z << 4) + 8);
+        let y = match self.preliminary_surface_level(x, z) {
+            i32::MAX => self.settings.sea_level,
+            y => y,
+        };
+
+        let biome = self.biome(x >> 2, y >> 2, z >> 2);
+        structure
+            .biomes
+            .contains(self.random_state.registry.as_ref(), BIOME, &biome)
+    }
+
+    fn safe_spawn_in_chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<BlockPos> {
+        (chunk_x << 4..(chunk_x << 4) + 16)
+            .flat_map(|x| (chunk_z << 4..(chunk_z << 4) + 16).map(move |z| (x, z)))
+            .find_map(|(x, z)| self.safe_spawn_in_column(x, z))
+    }
+
+    /// A spawn on top of the highest solid block, columns topped by a fluid
+    /// are rejected.
+    fn safe_spawn_in_column(&self, x: i32, z: i32) -> Option<BlockPos> {
+        let column = self.base_column(x, z);
+        let top = column
+            .iter()
+            .rposition(|&state| Heightmap::MotionBlocking.is_opaque(state))?;
+        match column[top].is_liquid() {
+            true => None,
+            false => Some(BlockPos::new(x, self.min_y() + top as i32 + 1, z)),
+        }
+    }
+
+    /// The block at height `y` with `final_density` sampled as `density`.
+    fn noise_block_state(&self, y: i32, density: f64) -> BlockState {
+        if density > 0.0 {
+            return self.settings.default_block;
+        }
+
+        // TODO: aquifers, this is what vanilla does with aquifers disabled
+        let sea_level = self.settings.sea_level;
+        if y < LAVA_LEVEL.min(sea_level) {
+            BlockState::LAVA
+        } else if y < sea_level {
+            self.settings.default_fluid
+        } else {
+            BlockState::AIR
+        }
+    }
+}
+
+type RegistryLoader = dyn Fn() -> eyre::Result<Arc<dyn Registry>> + Send + Sync;
+
+/// A [`ChunkGenerator`] that can be rebuilt from updated registry content.
+///
+/// Chunks should take a snapshot with [`current`](Self::current) when they
+/// start generating, a reload only affects snapshots taken after it. Every
+/// snapshot has its own registry from `load_registry`, content a snapshot
+/// resolves lazily, like structure sets and tags, comes from that registry.
+pub struct ReloadableGenerator {
+    settings: Ident<String>,
+    load_registry: Box<RegistryLoader>,
+    seed: i64,
+    current: RwLock<Arc<ChunkGenerator>>,
+}
+
+impl ReloadableGenerator {
+    pub fn new(
+        settings: Ident<String>,
+        biome_source: BiomeSource,
+        load_registry: impl Fn() -> eyre::Result<Arc<dyn Registry>> + Send + Sync + 'static,
+        seed: i64,
+    ) -> eyre::Result<Self> {
+        let generator = Self::build(&settings, biome_source, load_registry()?, seed)?;
+
+        Ok(Self {
+            settings,
+            load_registry: Box::new(load_registry),
+            seed,
+            current: RwLock::new(Arc::new(generator)),
+        })
+    }
+
+    /// The generator new chunks should use.
+    pub fn current(&self) -> Arc<ChunkGenerator> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Loads a fresh registry and compiles a new generator from it. The
+    /// current generator is kept if that fails, e.g. because a datapack file
+    /// is broken.
+    pub fn reload(&self) -> eyre::Result<()> {
+        let biome_source = self.current().biome_source.clone();
+        let generator = Self::build(
+            &self.settings,
+            biome_source,
+            (self.load_registry)()?,
+            self.seed,
+        )?;
+
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(generator);
+        Ok(())
+    }
+
+    fn build(
+        settings: &Ident<String>,
+        biome_source: BiomeSource,
+        registry: Arc<dyn Registry>,
+        seed: i64,
+    ) -> eyre::Result<ChunkGenerator> {
+        let settings = registry.noise_generator_settings(&settings.as_str_ident())?;
+        let random_state = Arc::new(RandomState::new(&settings, registry, seed));
+        ChunkGenerator::new(settings, biome_source, random_state)
+    }
+}
+
+/// `found` if it's closer to `origin` than `nearest`.
+fn nearer(
+    origin: BlockPos,
+    nearest: Option<(BlockPos, Ident<String>)>,
+    found: (BlockPos, Ident<String>),
+) -> (BlockPos, Ident<String>) {
+    match nearest {
+        Some(nearest)
+            if distance_squared(origin, nearest.0) <= distance_squared(origin, found.0) =>
+        {
+            nearest
+        }
+        _ => found,
+    }
+}
+
+fn distance_squared(a: BlockPos, b: BlockPos) -> i64 {
+    let (dx, dy, dz) = ((a.x - b.x) as i64, (a.y - b.y) as i64, (a.z - b.z) as i64);
+    dx * dx + dy * dy + dz * dz
+}
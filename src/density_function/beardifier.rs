@@ -0,0 +1,284 @@
+use std::sync::OnceLock;
+
+use valence_core::block_pos::BlockPos;
+
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction};
+use crate::structure::{JigsawJunction, RigidPiece, TerrainAdjustment};
+
+const BEARD_KERNEL_SIZE: i32 = 24;
+const BEARD_KERNEL_RADIUS: i32 = 12;
+
+/// Adapts terrain around structure pieces by beard or bury contributions.
+///
+/// `minecraft:beardifier` compiles to an empty instance, unless a chunk's
+/// router substitutes one built by [`Beardifier::for_chunk`], see
+/// [`ChunkGenerator::chunk_router`](crate::chunk_generator::ChunkGenerator::chunk_router).
+#[derive(Clone, Default)]
+pub struct Beardifier {
+    pieces: Vec<RigidPiece>,
+    junctions: Vec<JigsawJunction>,
+}
+
+impl Beardifier {
+    pub fn new(
+        pieces: Vec<RigidPiece>,
+        junctions: Vec<JigsawJunction>,
+    ) -> Box<dyn DensityFunction> {
+        Box::new(Self { pieces, junctions })
+    }
+
+    pub fn empty() -> Box<dyn DensityFunction> {
+        Box::<Self>::default()
+    }
+
+    /// Collects the pieces and junctions that are able to influence the
+    /// chunk at the given chunk coordinates.
+    pub fn for_chunk(
+        chunk_x: i32,
+        chunk_z: i32,
+        pieces: impl IntoIterator<Item = RigidPiece>,
+        junctions: impl IntoIterator<Item = JigsawJunction>,
+    ) -> Self {
+        let min_x = chunk_x << 4;
+        let min_z = chunk_z << 4;
+
+        let pieces = pieces
+            .into_iter()
+            .filter(|p| p.terrain_adjustment != TerrainAdjustment::None)
+            .filter(|p| {
+                p.bounding_box.intersects_xz(
+                    min_x - BEARD_KERNEL_RADIUS,
+                    min_z - BEARD_KERNEL_RADIUS,
+                    min_x + 15 + BEARD_KERNEL_RADIUS,
+                    min_z + 15 + BEARD_KERNEL_RADIUS,
+                )
+            })
+            .collect();
+
+        let junctions = junctions
+            .into_iter()
+            .filter(|j| {
+                j.source_x > min_x - BEARD_KERNEL_RADIUS
+                    && j.source_z > min_z - BEARD_KERNEL_RADIUS
+                    && j.source_x < min_x + 15 + BEARD_KERNEL_RADIUS
+                    && j.source_z < min_z + 15 + BEARD_KERNEL_RADIUS
+            })
+            .collect();
+
+        Self { pieces, junctions }
+    }
+
+    fn bury_contribution(x: f64, y: f64, z: f64) -> f64 {
+        let distance = (x * x + y * y + z * z).sqrt();
+        (1.0 - distance / 6.0).clamp(0.0, 1.0)
+    }
+
+    fn beard_contribution(x: i32, y: i32, z: i32, y_to_ground: i32) -> f64 {
+        let i = x + BEARD_KERNEL_RADIUS;
+        let j = y + BEARD_KERNEL_RADIUS;
+        let k = z + BEARD_KERNEL_RADIUS;
+
+        if !(in_kernel_range(i) && in_kernel_range(j) && in_kernel_range(k)) {
+            return 0.0;
+        }
+
+        let d = y_to_ground as f64 + 0.5;
+        let length_squared = (x * x) as f64 + d * d + (z * z) as f64;
+        let f = -d * fast_inv_sqrt(length_squared / 2.0) / 2.0;
+
+        let idx = (k * BEARD_KERNEL_SIZE * BEARD_KERNEL_SIZE + i * BEARD_KERNEL_SIZE + j) as usize;
+        f * beard_kernel()[idx] as f64
+    }
+}
+
+impl DensityFunction for Beardifier {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        let (x, y, z) = (pos.x, pos.y, pos.z);
+
+        // one running total like vanilla, the order of the additions matters
+        let mut d = 0.0;
+        for piece in &self.pieces {
+            let bb = &piece.bounding_box;
+            let dx = 0.max((bb.min.x - x).max(x - bb.max.x));
+            let dz = 0.max((bb.min.z - z).max(z - bb.max.z));
+            let ground_y = bb.min.y + piece.ground_level_delta;
+            let y_to_ground = y - ground_y;
+
+            let dy = match piece.terrain_adjustment {
+                TerrainAdjustment::None => 0,
+                TerrainAdjustment::Bury | TerrainAdjustment::BeardThin => y_to_ground,
+                TerrainAdjustment::BeardBox => 0.max((ground_y - y).max(y - bb.max.y)),
+                TerrainAdjustment::Encapsulate => 0.max((bb.min.y - y).max(y - bb.max.y)),
+            };
+
+            d += match piece.terrain_adjustment {
+                TerrainAdjustment::None => 0.0,
+                TerrainAdjustment::Bury => Self::bury_contribution(dx as f64, dy as f64, dz as f64),
+                TerrainAdjustment::BeardThin | TerrainAdjustment::BeardBox => {
+                    Self::beard_contribution(dx, dy, dz, y_to_ground) * 0.8
+                }
+                TerrainAdjustment::Encapsulate => {
+                    Self::bury_contribution(dx as f64 / 2.0, dy as f64 / 2.0, dz as f64 / 2.0) * 0.8
+                }
+            };
+        }
+        for junction in &self.junctions {
+            let dx = x - junction.source_x;
+            let dy = y - junction.source_ground_y;
+            let dz = z - junction.source_z;
+            d += Self::beard_contribution(dx, dy, dz, dy) * 0.4;
+        }
+
+        d
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        if self.pieces.is_empty() && self.junctions.is_empty() {
+            slice.iter_mut().for_each(|v| *v = 0.0);
+            return;
+        }
+
+        context_provider.fill_direct(slice, self)
+    }
+
+    fn min(&self) -> f64 {
+        if self.pieces.is_empty() && self.junctions.is_empty() {
+            0.0
+        } else {
+            f64::NEG_INFINITY
+        }
+    }
+
+    fn max(&self) -> f64 {
+        if self.pieces.is_empty() && self.junctions.is_empty() {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("beardifier")
+    }
+}
+
+fn in_kernel_range(i: i32) -> bool {
+    (0..BEARD_KERNEL_SIZE).contains(&i)
+}
+
+fn beard_kernel() -> &'static [f32] {
+    static KERNEL: OnceLock<Vec<f32>> = OnceLock::new();
+    KERNEL.get_or_init(|| {
+        let size = BEARD_KERNEL_SIZE as usize;
+        let mut kernel = vec![0.0_f32; size * size * size];
+
+        for z in 0..BEARD_KERNEL_SIZE {
+            for x in 0..BEARD_KERNEL_SIZE {
+                for y in 0..BEARD_KERNEL_SIZE {
+                    let idx = (z * BEARD_KERNEL_SIZE * BEARD_KERNEL_SIZE
+                        + x * BEARD_KERNEL_SIZE
+                        + y) as usize;
+                    kernel[idx] = compute_beard_contribution(
+                        x - BEARD_KERNEL_RADIUS,
+                        y - BEARD_KERNEL_RADIUS,
+                        z - BEARD_KERNEL_RADIUS,
+                    ) as f32;
+                }
+            }
+        }
+
+        kernel
+    })
+}
+
+fn compute_beard_contribution(x: i32, y: i32, z: i32) -> f64 {
+    let y = y as f64 + 0.5;
+    let length_squared = (x * x) as f64 + y * y + (z * z) as f64;
+    std::f64::consts::E.powf(-length_squared / 16.0)
+}
+
+/// Same approximation vanilla uses, results must match bit for bit.
+fn fast_inv_sqrt(x: f64) -> f64 {
+    let half = 0.5 * x;
+    let bits = 6910469410427058090_i64 - (x.to_bits() as i64 >> 1);
+    let y = f64::from_bits(bits as u64);
+    y * (1.5 - half * y * y)
+}
+
+#[cfg(test)]
+mod test {
+    use valence_core::block_pos::BlockPos;
+
+    use crate::density_function::beardifier::Beardifier;
+    use crate::density_function::DensityFunction;
+    use crate::structure::{
+        BoundingBox, JigsawJunction, Projection, RigidPiece, TerrainAdjustment,
+    };
+
+    fn piece(terrain_adjustment: TerrainAdjustment) -> RigidPiece {
+        RigidPiece {
+            bounding_box: BoundingBox::new(BlockPos::new(0, 64, 0), BlockPos::new(8, 72, 8)),
+            terrain_adjustment,
+            ground_level_delta: 1,
+        }
+    }
+
+    #[test]
+    fn beardifier_empty_test() {
+        let f = Beardifier::empty();
+        assert_eq!(0.0, f.compute(BlockPos::new(4, 64, 4)));
+        assert_eq!(0.0, f.min());
+        assert_eq!(0.0, f.max());
+    }
+
+    #[test]
+    fn beardifier_bury_test() {
+        let f = Beardifier::new(vec![piece(TerrainAdjustment::Bury)], vec![]);
+        assert_eq!(1.0, f.compute(BlockPos::new(4, 65, 4)));
+        assert_eq!(0.5, f.compute(BlockPos::new(4, 68, 4)));
+        assert_eq!(0.0, f.compute(BlockPos::new(4, 71, 4)));
+        assert_eq!(0.0, f.compute(BlockPos::new(20, 65, 4)));
+    }
+
+    #[test]
+    fn beardifier_beard_test() {
+        let f = Beardifier::new(vec![piece(TerrainAdjustment::BeardThin)], vec![]);
+        assert!(
+            f.compute(BlockPos::new(4, 64, 4)) > 0.0,
+            "fills below the piece"
+        );
+        assert!(
+            f.compute(BlockPos::new(4, 66, 4)) < 0.0,
+            "carves above the piece"
+        );
+        assert_eq!(0.0, f.compute(BlockPos::new(4, 100, 4)));
+    }
+
+    #[test]
+    fn beardifier_for_chunk_test() {
+        let junction = JigsawJunction {
+            source_x: 20,
+            source_ground_y: 64,
+            source_z: 20,
+            delta_y: 0,
+            dest_projection: Projection::TerrainMatching,
+        };
+
+        let f = Beardifier::for_chunk(
+            4,
+            4,
+            [
+                piece(TerrainAdjustment::Bury),
+                piece(TerrainAdjustment::None),
+            ],
+            [junction],
+        );
+        assert_eq!(0.0, f.compute(BlockPos::new(4, 65, 4)));
+        assert_eq!(0.0, f.min());
+
+        let f = Beardifier::for_chunk(0, 0, [piece(TerrainAdjustment::Bury)], [junction]);
+        assert_eq!(1.0, f.compute(BlockPos::new(4, 65, 4)));
+        assert!(f.compute(BlockPos::new(20, 63, 20)) > 0.0);
+    }
+}
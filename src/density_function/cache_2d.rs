@@ -1,5 +1,6 @@
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::describe::Description;
 use crate::density_function::{ContextProvider, DensityFunction};
 
 pub struct Cache2D(Box<dyn DensityFunction>);
@@ -27,4 +28,8 @@ impl DensityFunction for Cache2D {
     fn max(&self) -> f64 {
         self.0.max()
     }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("cache_2d").with_argument(self.0.as_ref())
+    }
 }
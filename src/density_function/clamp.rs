@@ -1,5 +1,6 @@
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::describe::Description;
 use crate::density_function::{ContextProvider, DensityFunction};
 
 pub struct Clamp {
@@ -33,4 +34,8 @@ impl DensityFunction for Clamp {
     fn max(&self) -> f64 {
         self.max
     }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("clamp").with_argument(self.f.as_ref())
+    }
 }
@@ -2,7 +2,8 @@ use std::iter::zip;
 
 use valence_core::block_pos::BlockPos;
 
-use crate::density_function::{ContextProvider, DensityFunction};
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction, SubsetContextProvider};
 
 #[derive(Copy, Clone)]
 pub enum Operation {
@@ -13,7 +14,7 @@ pub enum Operation {
 }
 
 impl Operation {
-    fn apply(&self, a: f64, b: f64) -> f64 {
+    pub(crate) fn apply(&self, a: f64, b: f64) -> f64 {
         match self {
             Operation::Add => a + b,
             Operation::Multiply => a * b,
@@ -22,39 +23,42 @@ impl Operation {
         }
     }
 
-    fn min(&self, a: &dyn DensityFunction, b: &dyn DensityFunction) -> f64 {
+    /// The bounds of the result for operands within `a` and `b`.
+    pub(crate) fn bounds(
+        &self,
+        (a_min, a_max): (f64, f64),
+        (b_min, b_max): (f64, f64),
+    ) -> (f64, f64) {
         match self {
-            Operation::Add => a.min() + b.min(),
-            Operation::Min => f64::min(a.min(), b.min()),
-            Operation::Max => f64::max(a.min(), b.min()),
+            Operation::Add => (a_min + b_min, a_max + b_max),
+            Operation::Min => (f64::min(a_min, b_min), f64::min(a_max, b_max)),
+            Operation::Max => (f64::max(a_min, b_min), f64::max(a_max, b_max)),
             Operation::Multiply => {
-                if a.min() > 0.0 && b.min() > 0.0 {
-                    a.min() * b.min()
-                } else if a.max() < 0.0 && b.max() < 0.0 {
-                    a.max() * b.max()
-                } else {
-                    f64::min(a.min() * b.max(), a.max() * b.min())
-                }
+                let corners = [a_min * b_min, a_min * b_max, a_max * b_min, a_max * b_max];
+                (
+                    corners.into_iter().fold(f64::INFINITY, f64::min),
+                    corners.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                )
             }
         }
     }
 
-    fn max(&self, a: &dyn DensityFunction, b: &dyn DensityFunction) -> f64 {
+    /// The result if the second operand within `b_min..=b_max` can't change
+    /// it, given the first operand `a`, like vanilla.
+    pub(crate) fn decided(&self, a: f64, b_min: f64, b_max: f64) -> Option<f64> {
         match self {
-            Operation::Add => a.max() + b.max(),
-            Operation::Min => f64::min(a.max(), b.max()),
-            Operation::Max => f64::max(a.max(), b.max()),
-            Operation::Multiply => {
-                if a.min() > 0.0 && b.min() > 0.0 {
-                    a.max() * b.max()
-                } else if a.max() < 0.0 && b.max() < 0.0 {
-                    a.min() * b.min()
-                } else {
-                    f64::max(a.min() * b.min(), a.max() * b.max())
-                }
-            }
+            Operation::Add => None,
+            Operation::Multiply if a == 0.0 => Some(0.0),
+            Operation::Min if a < b_min => Some(a),
+            Operation::Max if a > b_max => Some(a),
+            _ => None,
         }
     }
+
+    /// The result of the operation like [`Commutative`] computes it.
+    pub(crate) fn evaluate(&self, a: f64, b: f64) -> f64 {
+        self.decided(a, b, b).unwrap_or_else(|| self.apply(a, b))
+    }
 }
 
 pub struct Commutative {
@@ -71,42 +75,56 @@ impl Commutative {
         f2: Box<dyn DensityFunction>,
         operation: Operation,
     ) -> Box<dyn DensityFunction> {
+        let (min, max) = operation.bounds((f1.min(), f1.max()), (f2.min(), f2.max()));
         Box::new(Self {
-            min: operation.min(f1.as_ref(), f2.as_ref()),
-            max: operation.max(f1.as_ref(), f2.as_ref()),
+            min,
+            max,
             f1,
             f2,
             operation,
         })
     }
+
+    fn decided(&self, a: f64) -> Option<f64> {
+        self.operation.decided(a, self.f2.min(), self.f2.max())
+    }
 }
 
 impl DensityFunction for Commutative {
     fn compute(&self, pos: BlockPos) -> f64 {
-        self.operation
-            .apply(self.f1.compute(pos), self.f2.compute(pos))
+        let a = self.f1.compute(pos);
+        match self.decided(a) {
+            Some(v) => v,
+            None => self.operation.apply(a, self.f2.compute(pos)),
+        }
     }
 
     fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
-        let len = slice.len();
-
         let a = slice;
         self.f1.fill(a, context_provider);
 
-        // TODO: Optimization Commutative::fill
-        // maybe put second part of this fill into the Operation enum?
-        // there a cases where we do not need to evaluate f2
-        // there a cases where we do not need to evaluate f2 for the whole slice -> only
-        // evaluate at specific indexes e.g for Operation::MAX: in case we know
-        // the bigger values based on density function interval borders
-        // e.g for Operation::MIN: in case we know the smaller values based on density
-        // function interval borders e.g for Operation::MUL: only evaluate f2(x)
-        // when f1(x) does not evaluated to zero
-        let mut b = Vec::with_capacity(len);
-        b.resize(len, Default::default());
-        self.f2.fill(b.as_mut_slice(), context_provider);
-
-        zip(a.iter_mut(), b.iter()).for_each(|(a, &b)| *a = self.operation.apply(*a, b))
+        let mut indices = vec![];
+        for (i, a) in a.iter_mut().enumerate() {
+            match self.decided(*a) {
+                Some(v) => *a = v,
+                None => indices.push(i),
+            }
+        }
+        if indices.is_empty() {
+            return;
+        }
+
+        let mut b = vec![0.0; indices.len()];
+        match indices.len() == a.len() {
+            true => self.f2.fill(&mut b, context_provider),
+            false => self.f2.fill(
+                &mut b,
+                &SubsetContextProvider::new(context_provider, &indices),
+            ),
+        }
+        for (i, b) in zip(indices, b) {
+            a[i] = self.operation.apply(a[i], b);
+        }
     }
 
     fn min(&self) -> f64 {
@@ -116,4 +134,86 @@ impl DensityFunction for Commutative {
     fn max(&self) -> f64 {
         self.max
     }
+
+    fn describe(&self) -> Description<'_> {
+        let kind = match self.operation {
+            Operation::Add => "add",
+            Operation::Multiply => "mul",
+            Operation::Min => "min",
+            Operation::Max => "max",
+        };
+        Description::new(kind)
+            .with_argument(self.f1.as_ref())
+            .with_argument(self.f2.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::density_function::commutative::{Commutative, Operation};
+    use crate::density_function::test::{Column, Counting};
+    use crate::density_function::y_clamped_gradient::YClampedGradient;
+    use crate::density_function::{ContextProvider, DensityFunction};
+
+    fn check(f1: (i32, i32, f64, f64), f2: (i32, i32, f64, f64), operation: Operation) -> usize {
+        let gradient = |(from_y, to_y, from_value, to_value)| {
+            Counting::new(YClampedGradient::new(from_y, to_y, from_value, to_value).unwrap())
+        };
+        let (f1, f2) = (gradient(f1), gradient(f2));
+        let f = Commutative::new(Box::new(f1.clone()), Box::new(f2.clone()), operation);
+
+        let mut slice = [0.0; 384];
+        f.fill(&mut slice, &Column);
+        assert_eq!(384, f1.calls());
+        let evaluated = f2.calls();
+        // undecided positions are filled in one batch
+        assert_eq!((evaluated > 0) as usize, f2.fills());
+
+        for (i, v) in slice.into_iter().enumerate() {
+            let pos = Column.for_index(i);
+            assert_eq!(f.compute(pos).to_bits(), v.to_bits(), "at {pos:?}");
+            assert_eq!(
+                operation.apply(f1.compute(pos), f2.compute(pos)),
+                v,
+                "at {pos:?}"
+            );
+        }
+        evaluated
+    }
+
+    #[test]
+    fn commutative_fill_test() {
+        // `f1` is zero up to y = 0
+        assert_eq!(
+            319,
+            check(
+                (0, 320, 0.0, 1.0),
+                (-64, 320, -2.0, 3.0),
+                Operation::Multiply
+            )
+        );
+        // `f1` is below the minimum of `f2` of 0.5 below y = 64
+        assert_eq!(
+            256,
+            check((-64, 192, -0.5, 1.5), (-64, 320, 0.5, 1.0), Operation::Min)
+        );
+        // `f1` is above the maximum of `f2` of 0.5 below y = 64
+        assert_eq!(
+            256,
+            check((-64, 192, 1.5, -0.5), (-64, 320, 0.0, 0.5), Operation::Max)
+        );
+        assert_eq!(
+            384,
+            check((-64, 320, 0.0, 1.0), (-64, 320, 1.0, 0.0), Operation::Add)
+        );
+        // `f1` is never zero
+        assert_eq!(
+            384,
+            check(
+                (-64, 320, 1.0, 2.0),
+                (-64, 320, 1.0, 0.0),
+                Operation::Multiply
+            )
+        );
+    }
 }
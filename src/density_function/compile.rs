@@ -1,114 +1,297 @@
 use std::simd::{f64x4, i32x4};
+use std::sync::{Arc, PoisonError};
 
+use eyre::eyre;
 use valence_core::ident::Ident;
 
 use crate::density_function;
 use crate::density_function::abs::abs;
 use crate::density_function::add::add;
+use crate::density_function::beardifier::Beardifier;
 use crate::density_function::cache_2d::Cache2D;
 use crate::density_function::cache_once::CacheOnce;
 use crate::density_function::clamp::Clamp;
+use crate::density_function::commutative::Operation;
 use crate::density_function::constant::Constant;
 use crate::density_function::cube::cube;
+use crate::density_function::custom;
 use crate::density_function::deserialize::{DensityFunctionTree, InlineDensityFunctionTree};
+use crate::density_function::end_islands::EndIslands;
+use crate::density_function::find_top_surface::FindTopSurface;
 use crate::density_function::flat_cache::FlatCache;
 use crate::density_function::half_negative::half_negative;
+use crate::density_function::interpolated::Interpolated;
+use crate::density_function::invert::invert;
 use crate::density_function::max::max;
 use crate::density_function::min::min;
 use crate::density_function::mul::mul;
+use crate::density_function::noise::instantiate_noise;
 use crate::density_function::quarter_negative::quarter_negative;
 use crate::density_function::range_choice::RangeChoice;
+use crate::density_function::reference::Reference;
+use crate::density_function::simplify;
 use crate::density_function::square::square;
 use crate::density_function::squeeze::squeeze;
+use crate::density_function::tape;
+use crate::density_function::weird_scaled_sampler::WeirdScaledSampler;
 use crate::density_function::y_clamped_gradient::YClampedGradient;
 use crate::density_function::DensityFunction;
+use crate::noise::blended::BlendedNoise;
+use crate::noise::normal::NormalNoise;
+use crate::random::legacy::LegacyRandom;
 use crate::random::random_state::RandomState;
+use crate::random::Kind;
+
+/// How compiled density functions are evaluated, see
+/// [`RandomState::with_backend`]. Both give the same results bit for bit.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// A tree of functions, one virtual call per node and sample.
+    #[default]
+    Tree,
+    /// A flat tape of instructions over register slots, filling slices one
+    /// instruction at a time.
+    Tape,
+}
+
+/// Tracks the references followed while compiling a density function, to
+/// reject cycles and trees nested deeper than
+/// [`RandomState::with_max_compile_depth`] allows.
+pub struct CompileContext<'a> {
+    pub(crate) random_state: &'a RandomState,
+    references: Vec<String>,
+    depth: usize,
+    beardifier: Option<Beardifier>,
+    /// Whether a `minecraft:beardifier` was compiled, references containing
+    /// one depend on the chunk and aren't shared.
+    beardified: bool,
+}
+
+impl<'a> CompileContext<'a> {
+    pub fn new(random_state: &'a RandomState) -> Self {
+        Self {
+            random_state,
+            references: vec![],
+            depth: 0,
+            beardifier: None,
+            beardified: false,
+        }
+    }
+
+    /// Substitutes `minecraft:beardifier` with `beardifier`, like vanilla's
+    /// `NoiseChunk` does for the structures of a chunk.
+    pub fn with_beardifier(mut self, beardifier: Beardifier) -> Self {
+        self.beardifier = Some(beardifier);
+        self
+    }
+
+    /// The noise `id` of the random state being compiled with.
+    pub fn noise(&self, id: &Ident<&str>) -> eyre::Result<Arc<NormalNoise>> {
+        instantiate_noise(id, self.random_state)
+    }
+
+    pub(crate) fn enter(&mut self) -> eyre::Result<()> {
+        if self.depth >= self.random_state.max_compile_depth {
+            return Err(eyre!(
+                "density function nested deeper than {} levels, references: {}",
+                self.random_state.max_compile_depth,
+                self.chain()
+            ));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.depth -= 1;
+    }
+
+    pub(crate) fn push_reference(&mut self, id: &str) -> eyre::Result<()> {
+        if self.references.iter().any(|r| r == id) {
+            self.references.push(id.to_string());
+            let chain = self.chain();
+            self.references.pop();
+            return Err(eyre!("density function reference cycle: {chain}"));
+        }
+
+        self.references.push(id.to_string());
+        Ok(())
+    }
+
+    pub(crate) fn pop_reference(&mut self) {
+        self.references.pop();
+    }
+
+    /// Compiles the function `id` once per random state, later references
+    /// share it.
+    fn compile_reference(&mut self, id: &Ident<String>) -> eyre::Result<Box<dyn DensityFunction>> {
+        let cache = &self.random_state.compiled_cache;
+        if let Some(function) = cache.read().unwrap_or_else(PoisonError::into_inner).get(id) {
+            return Ok(Reference::new(id.clone(), function.clone()));
+        }
+
+        self.push_reference(id.as_str())?;
+        let outer = std::mem::take(&mut self.beardified);
+        let result = self
+            .random_state
+            .registry
+            .density_function(&id.as_str_ident())
+            .and_then(|f| f.compile_with(self));
+        let beardified = std::mem::replace(&mut self.beardified, outer);
+        self.beardified |= beardified;
+        self.pop_reference();
+
+        let function: Arc<dyn DensityFunction> = Arc::from(result?);
+        if beardified {
+            return Ok(Reference::new(id.clone(), function));
+        }
+        Ok(Reference::new(
+            id.clone(),
+            cache
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(id.clone())
+                .or_insert(function)
+                .clone(),
+        ))
+    }
+
+    fn commutative(
+        &mut self,
+        argument1: &DensityFunctionTree,
+        argument2: &DensityFunctionTree,
+        operation: Operation,
+    ) -> eyre::Result<Box<dyn DensityFunction>> {
+        let (f1, f2) = (argument1.compile_with(self)?, argument2.compile_with(self)?);
+        if self.random_state.simplify {
+            return Ok(simplify::commutative(f1, f2, operation));
+        }
+
+        Ok(match operation {
+            Operation::Add => add(f1, f2),
+            Operation::Multiply => mul(f1, f2),
+            Operation::Min => min(f1, f2),
+            Operation::Max => max(f1, f2),
+        })
+    }
+
+    fn chain(&self) -> String {
+        match self.references.is_empty() {
+            true => "none".to_string(),
+            false => self.references.join(" -> "),
+        }
+    }
+}
 
 impl DensityFunctionTree {
     pub fn compile(&self, random_state: &RandomState) -> eyre::Result<Box<dyn DensityFunction>> {
-        match self {
-            DensityFunctionTree::Constant(arg) => Ok(Constant::new(*arg)),
-            DensityFunctionTree::Reference(id) => random_state
-                .registry
-                .density_function(&Ident::new(id)?.as_str_ident())?
-                .compile(random_state),
-            DensityFunctionTree::Inline(f) => f.compile(random_state),
+        self.compile_in(&mut CompileContext::new(random_state))
+    }
+
+    /// Compiles this tree on its own with the backend of the random state
+    /// `ctx` compiles with.
+    pub fn compile_in(&self, ctx: &mut CompileContext) -> eyre::Result<Box<dyn DensityFunction>> {
+        match ctx.random_state.backend {
+            Backend::Tree => self.compile_with(ctx),
+            Backend::Tape => tape::compile(self, ctx),
         }
     }
+
+    /// Compiles this tree as part of the function `ctx` is compiling, e.g.
+    /// the arguments of a [custom type](crate::density_function::custom).
+    pub fn compile_with(&self, ctx: &mut CompileContext) -> eyre::Result<Box<dyn DensityFunction>> {
+        ctx.enter()?;
+        let result = match self {
+            DensityFunctionTree::Constant(arg) => Ok(Constant::new(*arg)),
+            DensityFunctionTree::Reference(id) => {
+                let id = Ident::new(id.as_str())?.to_string_ident();
+                ctx.compile_reference(&id)
+            }
+            DensityFunctionTree::Inline(f) => f.compile_with(ctx),
+            DensityFunctionTree::Custom(f) => custom::compile(f, ctx),
+        };
+        ctx.leave();
+        result
+    }
 }
 
 impl InlineDensityFunctionTree {
     pub fn compile(&self, random_state: &RandomState) -> eyre::Result<Box<dyn DensityFunction>> {
+        let ctx = &mut CompileContext::new(random_state);
+        match random_state.backend {
+            Backend::Tree => self.compile_with(ctx),
+            Backend::Tape => tape::compile_inline(self, ctx),
+        }
+    }
+
+    pub(crate) fn compile_with(
+        &self,
+        ctx: &mut CompileContext,
+    ) -> eyre::Result<Box<dyn DensityFunction>> {
+        let random_state = ctx.random_state;
         match self {
             InlineDensityFunctionTree::Constant { argument } => Ok(Constant::new(*argument)),
 
-            InlineDensityFunctionTree::Abs { argument } => Ok(abs(argument.compile(random_state)?)),
+            InlineDensityFunctionTree::Abs { argument } => Ok(abs(argument.compile_with(ctx)?)),
             InlineDensityFunctionTree::Square { argument } => {
-                Ok(square(argument.compile(random_state)?))
-            }
-            InlineDensityFunctionTree::Cube { argument } => {
-                Ok(cube(argument.compile(random_state)?))
+                Ok(square(argument.compile_with(ctx)?))
             }
+            InlineDensityFunctionTree::Cube { argument } => Ok(cube(argument.compile_with(ctx)?)),
             InlineDensityFunctionTree::HalfNegative { argument } => {
-                Ok(half_negative(argument.compile(random_state)?))
+                Ok(half_negative(argument.compile_with(ctx)?))
             }
             InlineDensityFunctionTree::QuarterNegative { argument } => {
-                Ok(quarter_negative(argument.compile(random_state)?))
+                Ok(quarter_negative(argument.compile_with(ctx)?))
             }
             InlineDensityFunctionTree::Squeeze { argument } => {
-                Ok(squeeze(argument.compile(random_state)?))
+                Ok(squeeze(argument.compile_with(ctx)?))
+            }
+            InlineDensityFunctionTree::Invert { argument } => {
+                Ok(invert(argument.compile_with(ctx)?))
             }
 
             InlineDensityFunctionTree::Max {
                 argument1,
                 argument2,
-            } => Ok(max(
-                argument1.compile(random_state)?,
-                argument2.compile(random_state)?,
-            )),
+            } => ctx.commutative(argument1, argument2, Operation::Max),
             InlineDensityFunctionTree::Min {
                 argument1,
                 argument2,
-            } => Ok(min(
-                argument1.compile(random_state)?,
-                argument2.compile(random_state)?,
-            )),
+            } => ctx.commutative(argument1, argument2, Operation::Min),
             InlineDensityFunctionTree::Add {
                 argument1,
                 argument2,
-            } => Ok(add(
-                argument1.compile(random_state)?,
-                argument2.compile(random_state)?,
-            )),
+            } => ctx.commutative(argument1, argument2, Operation::Add),
             InlineDensityFunctionTree::Mul {
                 argument1,
                 argument2,
-            } => Ok(mul(
-                argument1.compile(random_state)?,
-                argument2.compile(random_state)?,
-            )),
+            } => ctx.commutative(argument1, argument2, Operation::Multiply),
 
-            InlineDensityFunctionTree::Clamp { input, min, max } => {
-                Ok(Clamp::new(input.compile(random_state)?, *min, *max))
-            }
+            InlineDensityFunctionTree::Clamp { input, min, max } => match ctx.random_state.simplify
+            {
+                true => {
+                    let (input, min, max) = simplify::nested_clamps(input, *min, *max);
+                    Ok(simplify::clamp(input.compile_with(ctx)?, min, max))
+                }
+                false => Ok(Clamp::new(input.compile_with(ctx)?, *min, *max)),
+            },
 
             InlineDensityFunctionTree::Cache2D { argument } => {
-                Ok(Cache2D::new(argument.compile(random_state)?))
+                Ok(Cache2D::new(argument.compile_with(ctx)?))
             }
             InlineDensityFunctionTree::FlatCache { argument } => {
-                Ok(FlatCache::new(argument.compile(random_state)?))
+                Ok(FlatCache::new(argument.compile_with(ctx)?))
             }
             InlineDensityFunctionTree::CacheOnce { argument } => {
-                Ok(CacheOnce::new(argument.compile(random_state)?))
-            }
-            InlineDensityFunctionTree::CacheAllInCell { argument } => {
-                println!("CacheAllInCell");
-                todo!()
-            }
-            InlineDensityFunctionTree::Interpolated { argument } => {
-                println!("Interpolated");
-                todo!()
+                Ok(CacheOnce::new(argument.compile_with(ctx)?))
             }
+            InlineDensityFunctionTree::CacheAllInCell { argument } => argument.compile_with(ctx),
+            InlineDensityFunctionTree::Interpolated { argument } => Ok(Interpolated::new(
+                argument.compile_with(ctx)?,
+                random_state.noise_settings.cell_width(),
+                random_state.noise_settings.cell_height(),
+            )),
 
             InlineDensityFunctionTree::Noise {
                 noise,
@@ -152,9 +335,9 @@ impl InlineDensityFunctionTree {
                 random_state,
                 1.0,
                 f64x4::from_array([*xz_scale, *y_scale, *xz_scale, 0.0]),
-                shift_x.compile(random_state)?,
-                shift_y.compile(random_state)?,
-                shift_z.compile(random_state)?,
+                shift_x.compile_with(ctx)?,
+                shift_y.compile_with(ctx)?,
+                shift_z.compile_with(ctx)?,
             ),
 
             InlineDensityFunctionTree::RangeChoice {
@@ -163,13 +346,19 @@ impl InlineDensityFunctionTree {
                 max_exclusive,
                 when_in_range,
                 when_out_of_range,
-            } => Ok(RangeChoice::new(
-                input.compile(random_state)?,
-                *min_inclusive,
-                *max_exclusive,
-                when_in_range.compile(random_state)?,
-                when_out_of_range.compile(random_state)?,
-            )),
+            } => {
+                let build = match random_state.simplify {
+                    true => simplify::range_choice,
+                    false => RangeChoice::new,
+                };
+                Ok(build(
+                    input.compile_with(ctx)?,
+                    *min_inclusive,
+                    *max_exclusive,
+                    when_in_range.compile_with(ctx)?,
+                    when_out_of_range.compile_with(ctx)?,
+                ))
+            }
             InlineDensityFunctionTree::YClampedGradient {
                 from_y,
                 to_y,
@@ -181,30 +370,199 @@ impl InlineDensityFunctionTree {
                 *from_value,
                 *to_value,
             )?),
-            InlineDensityFunctionTree::Spline { spline } => {
-                Ok(Box::new(spline.compile(random_state)?))
-            }
+            InlineDensityFunctionTree::Spline { spline } => Ok(Box::new(spline.compile_with(ctx)?)),
+            InlineDensityFunctionTree::EndIslands {} => Ok(EndIslands::new(random_state.seed)),
+            InlineDensityFunctionTree::FindTopSurface {
+                density,
+                upper_bound,
+                lower_bound,
+                cell_height,
+            } => FindTopSurface::new(
+                density.compile_with(ctx)?,
+                upper_bound.compile_with(ctx)?,
+                *lower_bound,
+                *cell_height,
+            ),
 
             InlineDensityFunctionTree::WeirdScaledSampler {
                 noise,
                 input,
                 rarity_value_mapper,
-            } => todo!(),
+            } => Ok(WeirdScaledSampler::new(
+                input.compile_with(ctx)?,
+                noise.clone(),
+                instantiate_noise(&noise.as_str_ident(), random_state)?,
+                *rarity_value_mapper,
+            )),
+
+            // Substituted with the structures of a chunk during chunk generation
+            InlineDensityFunctionTree::Beardifier {} => {
+                ctx.beardified = true;
+                Ok(Box::new(ctx.beardifier.clone().unwrap_or_default()))
+            }
 
             // Blending
             InlineDensityFunctionTree::BlendOffset {} => Ok(Constant::new(0.0)), // ???
             InlineDensityFunctionTree::BlendAlpha {} => Ok(Constant::new(1.0)),  // ???
-            InlineDensityFunctionTree::BlendDensity { argument } => todo!(),
+            InlineDensityFunctionTree::BlendDensity { argument } => argument.compile_with(ctx),
             InlineDensityFunctionTree::OldBlendNoise {
                 xz_scale,
                 y_scale,
                 xz_factor,
                 y_factor,
                 smear_scale_multiplier,
-            } => todo!(),
+            } => {
+                let mut r = match random_state.random.kind() {
+                    Kind::LegacyRandom => LegacyRandom::new(random_state.seed),
+                    Kind::Xoroshiro => random_state.random.with_hash_of("minecraft:terrain"),
+                };
+
+                Ok(Box::new(BlendedNoise::new(
+                    r.as_mut(),
+                    *xz_scale,
+                    *y_scale,
+                    *xz_factor,
+                    *y_factor,
+                    *smear_scale_multiplier,
+                )?))
+            }
+
+            // needs the slide settings 1.18 noise settings had
+            InlineDensityFunctionTree::Slide { .. } => Err(eyre!(
+                "minecraft:slide was removed in 1.19 and is not supported"
+            )),
+        }
+    }
+}
 
-            #[deprecated]
-            InlineDensityFunctionTree::Slide { argument } => todo!(),
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use valence_core::block_pos::BlockPos;
+    use valence_core::ident;
+    use valence_core::ident::Ident;
+
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::density_function::noise::instantiate_noise;
+    use crate::noise::deserialize::{NoiseParameters, NoiseSettings};
+    use crate::random::random_state::RandomState;
+    use crate::random::Kind;
+    use crate::registry::memory::MemoryRegistry;
+
+    fn random_state(functions: &[(&str, &str)]) -> RandomState {
+        let registry = MemoryRegistry::new()
+            .with_noise(&ident!("test:noise"), NoiseParameters::new(-3, vec![1.0]));
+        for (id, json) in functions {
+            registry.insert_density_function(
+                &Ident::new(*id).unwrap().as_str_ident(),
+                serde_json::from_str::<DensityFunctionTree>(json)
+                    .expect("should be a valid density function"),
+            );
         }
+
+        let noise_settings = NoiseSettings {
+            min_y: -64,
+            height: 384,
+            xz_size: 1,
+            y_size: 2,
+        };
+        RandomState::from_parts(noise_settings, &Kind::Xoroshiro, Arc::new(registry), 0)
+    }
+
+    fn compile(random_state: &RandomState, id: &str) -> eyre::Result<()> {
+        DensityFunctionTree::Reference(id.to_string())
+            .compile(random_state)
+            .map(|_| ())
+    }
+
+    #[test]
+    fn reference_cycle_test() {
+        let random_state = random_state(&[
+            (
+                "test:a",
+                r#"{"type": "minecraft:abs", "argument": "test:b"}"#,
+            ),
+            (
+                "test:b",
+                r#"{"type": "minecraft:add", "argument1": 1, "argument2": "test:a"}"#,
+            ),
+            (
+                "test:self",
+                r#"{"type": "minecraft:square", "argument": "test:self"}"#,
+            ),
+            (
+                "test:diamond",
+                r#"{"type": "minecraft:mul", "argument1": "test:leaf", "argument2": "test:leaf"}"#,
+            ),
+            ("test:leaf", "0.5"),
+        ]);
+
+        let cycle = compile(&random_state, "test:a").unwrap_err();
+        assert!(cycle.to_string().contains("test:a -> test:b -> test:a"));
+        let cycle = compile(&random_state, "test:self").unwrap_err();
+        assert!(cycle.to_string().contains("test:self -> test:self"));
+
+        assert!(compile(&random_state, "test:diamond").is_ok());
+    }
+
+    #[test]
+    fn max_compile_depth_test() {
+        let functions = (0..16)
+            .map(|i| {
+                (
+                    format!("test:f{i}"),
+                    format!(
+                        r#"{{"type": "minecraft:abs", "argument": "test:f{}"}}"#,
+                        i + 1
+                    ),
+                )
+            })
+            .chain([("test:f16".to_string(), "1.0".to_string())])
+            .collect::<Vec<_>>();
+        let functions = functions
+            .iter()
+            .map(|(id, json)| (id.as_str(), json.as_str()))
+            .collect::<Vec<_>>();
+
+        assert!(compile(&random_state(&functions), "test:f0").is_ok());
+
+        let deep = compile(
+            &random_state(&functions).with_max_compile_depth(20),
+            "test:f0",
+        )
+        .unwrap_err();
+        assert!(deep.to_string().contains("deeper than 20 levels"));
+        assert!(deep.to_string().contains("test:f0 -> test:f1"));
+    }
+
+    #[test]
+    fn shared_reference_test() {
+        let random_state = random_state(&[
+            (
+                "test:a",
+                r#"{"type": "minecraft:add", "argument1": "test:n", "argument2": "test:n"}"#,
+            ),
+            (
+                "test:n",
+                r#"{"type": "minecraft:noise", "noise": "test:noise", "xz_scale": 1, "y_scale": 1}"#,
+            ),
+        ]);
+
+        let a = DensityFunctionTree::Reference("test:a".to_string())
+            .compile(&random_state)
+            .expect("should compile");
+        let n = DensityFunctionTree::Reference("test:n".to_string())
+            .compile(&random_state)
+            .expect("should compile");
+        assert_eq!(2, random_state.compiled_cache.read().unwrap().len());
+        assert_eq!(1, random_state.noise_instance_cache.read().unwrap().len());
+
+        let pos = BlockPos::new(13, 7, -42);
+        assert_eq!(2.0 * n.compute(pos), a.compute(pos));
+        assert!(Arc::ptr_eq(
+            &instantiate_noise(&ident!("test:noise"), &random_state).unwrap(),
+            &instantiate_noise(&ident!("test:noise"), &random_state).unwrap()
+        ));
     }
 }
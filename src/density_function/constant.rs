@@ -1,5 +1,6 @@
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::describe::Description;
 use crate::density_function::{ContextProvider, DensityFunction};
 
 pub struct Constant(f64);
@@ -26,4 +27,12 @@ impl DensityFunction for Constant {
     fn max(&self) -> f64 {
         self.0
     }
+
+    fn as_constant(&self) -> Option<f64> {
+        Some(self.0)
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("constant")
+    }
 }
@@ -1,8 +1,6 @@
-use std::sync::Arc;
-
-use crate::density_function::transformer::Transformer;
+use crate::density_function::transformer::Mapper;
 use crate::density_function::DensityFunction;
 
 pub fn cube(f: Box<dyn DensityFunction>) -> Box<dyn DensityFunction> {
-    Transformer::new(f, Arc::new(|x: f64| x.powi(3)))
+    Mapper::Cube.map(f)
 }
@@ -0,0 +1,194 @@
+//! Density function types added at runtime, next to the vanilla ones.
+//!
+//! ```ignore
+//! #[derive(Deserialize)]
+//! struct Scaled {
+//!     argument: Arc<DensityFunctionTree>,
+//!     scale: f64,
+//! }
+//!
+//! custom::register(ident!("ourserver:scaled").to_string_ident(), |f: Scaled, ctx| {
+//!     let argument = f.argument.compile_with(ctx)?;
+//!     Ok(Box::new(ScaledFunction::new(argument, f.scale)))
+//! })?;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+use eyre::eyre;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use valence_core::ident::Ident;
+
+use crate::density_function::compile::CompileContext;
+use crate::density_function::deserialize::CustomDensityFunctionTree;
+use crate::density_function::DensityFunction;
+
+type CompileFn =
+    dyn Fn(&Value, &mut CompileContext) -> eyre::Result<Box<dyn DensityFunction>> + Send + Sync;
+
+fn types() -> &'static RwLock<HashMap<Ident<String>, Arc<CompileFn>>> {
+    static TYPES: OnceLock<RwLock<HashMap<Ident<String>, Arc<CompileFn>>>> = OnceLock::new();
+    TYPES.get_or_init(Default::default)
+}
+
+/// Registers the density function type `id` for every random state. Its
+/// JSON, `type` field excluded, is deserialized to `T` and compiled with
+/// `compile`, which should compile nested density functions with
+/// [`DensityFunctionTree::compile_with`](crate::density_function::deserialize::DensityFunctionTree::compile_with).
+///
+/// Registering `id` again replaces the previous type. The `minecraft`
+/// namespace is reserved for the vanilla types.
+pub fn register<T: DeserializeOwned>(
+    id: Ident<String>,
+    compile: impl Fn(T, &mut CompileContext) -> eyre::Result<Box<dyn DensityFunction>>
+        + Send
+        + Sync
+        + 'static,
+) -> eyre::Result<()> {
+    if id.namespace() == "minecraft" {
+        return Err(eyre!(
+            "can't register density function type {id}, the minecraft namespace is reserved"
+        ));
+    }
+
+    let name = id.clone();
+    let compile: Arc<CompileFn> = Arc::new(move |arguments, ctx| {
+        let arguments = T::deserialize(arguments)
+            .map_err(|e| eyre!("invalid arguments for density function type {name}: {e}"))?;
+        compile(arguments, ctx)
+    });
+    types()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(id, compile);
+    Ok(())
+}
+
+pub(crate) fn compile(
+    f: &CustomDensityFunctionTree,
+    ctx: &mut CompileContext,
+) -> eyre::Result<Box<dyn DensityFunction>> {
+    let compile = types()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&f.kind)
+        .cloned()
+        .ok_or_else(|| eyre!("unknown density function type {}", f.kind))?;
+    compile(&Value::Object(f.arguments.clone()), ctx)
+}
+
+/// Keeps vanilla type names out of [`CustomDensityFunctionTree`], so a
+/// malformed vanilla function fails to deserialize instead of failing to
+/// compile as an unknown custom type.
+pub(crate) fn deserialize_kind<'de, D>(d: D) -> Result<Ident<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let kind = Ident::<String>::deserialize(d)?;
+    match kind.namespace() {
+        "minecraft" => Err(serde::de::Error::custom(format!(
+            "invalid minecraft density function {kind}"
+        ))),
+        _ => Ok(kind),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde::Deserialize;
+    use serde_json::json;
+    use valence_core::block_pos::BlockPos;
+    use valence_core::ident;
+
+    use crate::density_function::compile::Backend;
+    use crate::density_function::constant::Constant;
+    use crate::density_function::custom;
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::density_function::mul::mul;
+    use crate::noise::deserialize::NoiseSettings;
+    use crate::random::random_state::RandomState;
+    use crate::random::Kind;
+    use crate::registry::memory::MemoryRegistry;
+
+    #[derive(Deserialize)]
+    struct Scaled {
+        argument: Arc<DensityFunctionTree>,
+        scale: f64,
+    }
+
+    fn random_state(backend: Backend) -> RandomState {
+        let noise_settings = NoiseSettings {
+            min_y: -64,
+            height: 384,
+            xz_size: 1,
+            y_size: 2,
+        };
+        RandomState::from_parts(
+            noise_settings,
+            &Kind::Xoroshiro,
+            Arc::new(MemoryRegistry::new()),
+            0,
+        )
+        .with_backend(backend)
+    }
+
+    #[test]
+    fn custom_type_test() {
+        custom::register(ident!("test:scaled").to_string_ident(), |f: Scaled, ctx| {
+            Ok(mul(Constant::new(f.scale), f.argument.compile_with(ctx)?))
+        })
+        .unwrap();
+
+        let json = json!({
+            "type": "minecraft:add",
+            "argument1": {
+                "type": "test:scaled",
+                "argument": {"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": 1.0, "to_value": -1.0},
+                "scale": 3.0
+            },
+            "argument2": 1.0
+        });
+        let tree: DensityFunctionTree = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(json, serde_json::to_value(&tree).unwrap());
+
+        for backend in [Backend::Tree, Backend::Tape] {
+            let f = tree.compile(&random_state(backend)).unwrap();
+            assert_eq!(4.0, f.compute(BlockPos::new(0, -64, 0)));
+            assert_eq!(-2.0, f.compute(BlockPos::new(0, 320, 0)));
+        }
+
+        let invalid = json!({"type": "test:scaled", "argument": 1.0});
+        assert!(serde_json::from_value::<DensityFunctionTree>(invalid)
+            .unwrap()
+            .compile(&random_state(Backend::Tree))
+            .is_err());
+    }
+
+    #[test]
+    fn unknown_type_test() {
+        let unknown = json!({"type": "test:unknown", "argument": 1.0});
+        let error = serde_json::from_value::<DensityFunctionTree>(unknown)
+            .unwrap()
+            .compile(&random_state(Backend::Tree))
+            .err()
+            .expect("should not compile");
+        assert_eq!(
+            "unknown density function type test:unknown",
+            error.to_string()
+        );
+
+        let vanilla = json!({"type": "minecraft:abs"});
+        assert!(serde_json::from_value::<DensityFunctionTree>(vanilla).is_err());
+        assert!(
+            custom::register(ident!("minecraft:abs").to_string_ident(), |_: (), _| {
+                Ok(Constant::new(0.0))
+            })
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Human-readable dumps of compiled density functions, for debugging.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use valence_core::ident::Ident;
+
+use crate::density_function::DensityFunction;
+
+/// A node of a compiled density function, see [`DensityFunction::describe`].
+pub struct Description<'a> {
+    /// The vanilla type of the node, e.g. `add`, or the Rust type name of
+    /// types vanilla doesn't have.
+    pub kind: &'static str,
+    /// The noise the node samples.
+    pub noise: Option<&'a Ident<String>>,
+    /// The id of the reference the node was compiled from.
+    pub reference: Option<&'a Ident<String>>,
+    pub arguments: Vec<&'a dyn DensityFunction>,
+}
+
+impl<'a> Description<'a> {
+    pub fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            noise: None,
+            reference: None,
+            arguments: vec![],
+        }
+    }
+
+    pub fn with_noise(mut self, noise: &'a Ident<String>) -> Self {
+        self.noise = Some(noise);
+        self
+    }
+
+    pub fn with_reference(mut self, reference: &'a Ident<String>) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    pub fn with_argument(mut self, argument: &'a dyn DensityFunction) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+}
+
+/// Identifies a node by address, functions shared by several references are
+/// the same node. The kind tells apart zero-sized nodes.
+fn key(f: &dyn DensityFunction, description: &Description) -> (*const (), &'static str) {
+    (
+        f as *const dyn DensityFunction as *const (),
+        description.kind,
+    )
+}
+
+fn label(f: &dyn DensityFunction, description: &Description, separator: &str) -> String {
+    let mut label = description.kind.to_string();
+    for id in [description.reference, description.noise]
+        .into_iter()
+        .flatten()
+    {
+        write!(label, " {id}").unwrap();
+    }
+    write!(label, "{separator}[{}, {}]", f.min(), f.max()).unwrap();
+    label
+}
+
+/// `f` as an indented tree, one node per line with its bounds. Nodes with
+/// arguments are expanded the first time they're reached only.
+pub fn to_text(f: &dyn DensityFunction) -> String {
+    fn visit(
+        f: &dyn DensityFunction,
+        depth: usize,
+        seen: &mut HashSet<(*const (), &'static str)>,
+        out: &mut String,
+    ) {
+        let description = f.describe();
+        let seen_before = !seen.insert(key(f, &description));
+        write!(out, "{:width$}", "", width = depth * 2).unwrap();
+        out.push_str(&label(f, &description, " "));
+        if seen_before && !description.arguments.is_empty() {
+            out.push_str(" (see above)\n");
+            return;
+        }
+
+        out.push('\n');
+        for argument in description.arguments {
+            visit(argument, depth + 1, seen, out);
+        }
+    }
+
+    let mut out = String::new();
+    visit(f, 0, &mut HashSet::new(), &mut out);
+    out
+}
+
+/// `f` as a Graphviz DOT graph, every node once with edges to its arguments
+/// in order.
+pub fn to_dot(f: &dyn DensityFunction) -> String {
+    fn visit(
+        f: &dyn DensityFunction,
+        nodes: &mut HashMap<(*const (), &'static str), usize>,
+        out: &mut String,
+    ) -> usize {
+        let description = f.describe();
+        let key = key(f, &description);
+        if let Some(&node) = nodes.get(&key) {
+            return node;
+        }
+
+        let node = nodes.len();
+        nodes.insert(key, node);
+        let label = label(f, &description, "\n")
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        writeln!(out, "    n{node} [label=\"{label}\"];").unwrap();
+
+        for argument in description.arguments {
+            let argument = visit(argument, nodes, out);
+            writeln!(out, "    n{node} -> n{argument};").unwrap();
+        }
+        node
+    }
+
+    let mut out = "digraph {\n    node [shape=box];\n".to_string();
+    visit(f, &mut HashMap::new(), &mut out);
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use valence_core::ident;
+    use valence_core::ident::Ident;
+
+    use crate::density_function::compile::Backend;
+    use crate::density_function::describe::{to_dot, to_text};
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::density_function::DensityFunction;
+    use crate::noise::deserialize::{NoiseParameters, NoiseSettings};
+    use crate::random::random_state::RandomState;
+    use crate::random::Kind;
+    use crate::registry::memory::MemoryRegistry;
+
+    fn random_state() -> RandomState {
+        let registry = MemoryRegistry::new()
+            .with_noise(&ident!("test:noise"), NoiseParameters::new(-3, vec![1.0]));
+        registry.insert_density_function(
+            &Ident::new("test:a").unwrap().as_str_ident(),
+            serde_json::from_str::<DensityFunctionTree>(
+                r#"{"type": "minecraft:abs", "argument": {"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": -1, "to_value": 1}}"#,
+            )
+            .unwrap(),
+        );
+
+        let noise_settings = NoiseSettings {
+            min_y: -64,
+            height: 384,
+            xz_size: 1,
+            y_size: 2,
+        };
+        RandomState::from_parts(noise_settings, &Kind::Xoroshiro, Arc::new(registry), 0)
+            .with_simplification(false)
+    }
+
+    fn compile(random_state: &RandomState, json: &str) -> Box<dyn DensityFunction> {
+        serde_json::from_str::<DensityFunctionTree>(json)
+            .unwrap()
+            .compile(random_state)
+            .unwrap()
+    }
+
+    #[test]
+    fn describe_test() {
+        let random_state = random_state();
+        let f = compile(
+            &random_state,
+            r#"{"type": "minecraft:add", "argument1": "test:a", "argument2": {"type": "minecraft:mul", "argument1": "test:a", "argument2": 2}}"#,
+        );
+
+        assert_eq!(
+            "add [0, 3]
+  reference test:a [0, 1]
+    abs [0, 1]
+      y_clamped_gradient [-1, 1]
+  mul [0, 2]
+    reference test:a [0, 1]
+      abs [0, 1] (see above)
+    constant [2, 2]
+",
+            to_text(f.as_ref())
+        );
+        assert_eq!(
+            r#"digraph {
+    node [shape=box];
+    n0 [label="add\n[0, 3]"];
+    n1 [label="reference test:a\n[0, 1]"];
+    n2 [label="abs\n[0, 1]"];
+    n3 [label="y_clamped_gradient\n[-1, 1]"];
+    n2 -> n3;
+    n1 -> n2;
+    n0 -> n1;
+    n4 [label="mul\n[0, 2]"];
+    n5 [label="reference test:a\n[0, 1]"];
+    n5 -> n2;
+    n4 -> n5;
+    n6 [label="constant\n[2, 2]"];
+    n4 -> n6;
+    n0 -> n4;
+}
+"#,
+            to_dot(f.as_ref())
+        );
+
+        let noise = compile(
+            &random_state,
+            r#"{"type": "minecraft:noise", "noise": "test:noise", "xz_scale": 1, "y_scale": 1}"#,
+        );
+        let description = noise.describe();
+        assert_eq!("noise", description.kind);
+        assert_eq!(Some("test:noise"), description.noise.map(|id| id.as_str()));
+
+        let tape = compile(
+            &random_state.with_backend(Backend::Tape),
+            r#"{"type": "minecraft:mul", "argument1": "test:a", "argument2": {"type": "minecraft:noise", "noise": "test:noise", "xz_scale": 1, "y_scale": 1}}"#,
+        );
+        assert_eq!(
+            r#"tape [-3.333333333333333, 3.333333333333333]
+  mul [-3.333333333333333, 3.333333333333333]
+    reference test:a [0, 1]
+      abs [0, 1]
+        y_clamped_gradient [-1, 1]
+    noise test:noise [-3.333333333333333, 3.333333333333333]
+"#,
+            to_text(tape.as_ref())
+        );
+    }
+}
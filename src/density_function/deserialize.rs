@@ -1,19 +1,26 @@
 use std::sync::Arc;
 
-use serde::Deserialize;
+use eyre::eyre;
+use serde::{Deserialize, Serialize};
 use valence_core::ident::Ident;
 
+use crate::density_function::custom;
+use crate::registry::Registry;
 use crate::spline::{Blueprint, CubicSpline};
 
-#[derive(Deserialize)]
+/// A density function as written in datapacks. Serializing it writes the
+/// same JSON, see [`inline_references`](Self::inline_references) to write
+/// it without references.
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(untagged)]
 pub enum DensityFunctionTree {
     Constant(f64),
     Reference(String),
     Inline(InlineDensityFunctionTree),
+    Custom(CustomDensityFunctionTree),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(tag = "type")]
 pub enum InlineDensityFunctionTree {
     #[serde(rename = "minecraft:abs")]
@@ -25,6 +32,9 @@ pub enum InlineDensityFunctionTree {
         argument2: Arc<DensityFunctionTree>,
     },
 
+    #[serde(rename = "minecraft:beardifier")]
+    Beardifier {},
+
     #[serde(rename = "minecraft:blend_density")]
     BlendDensity { argument: Arc<DensityFunctionTree> },
 
@@ -37,6 +47,17 @@ pub enum InlineDensityFunctionTree {
     #[serde(rename = "minecraft:cache_once")]
     CacheOnce { argument: Arc<DensityFunctionTree> },
 
+    #[serde(rename = "minecraft:end_islands")]
+    EndIslands {},
+
+    #[serde(rename = "minecraft:find_top_surface")]
+    FindTopSurface {
+        density: Arc<DensityFunctionTree>,
+        upper_bound: Arc<DensityFunctionTree>,
+        lower_bound: i32,
+        cell_height: i32,
+    },
+
     #[serde(rename = "minecraft:flat_cache")]
     FlatCache { argument: Arc<DensityFunctionTree> },
 
@@ -59,6 +80,9 @@ pub enum InlineDensityFunctionTree {
     #[serde(rename = "minecraft:interpolated")]
     Interpolated { argument: Arc<DensityFunctionTree> },
 
+    #[serde(rename = "minecraft:invert")]
+    Invert { argument: Arc<DensityFunctionTree> },
+
     #[serde(rename = "minecraft:max")]
     Max {
         argument1: Arc<DensityFunctionTree>,
@@ -90,7 +114,7 @@ pub enum InlineDensityFunctionTree {
         y_scale: f64,
         xz_factor: f64,
         y_factor: f64,
-        smear_scale_multiplier: u8,
+        smear_scale_multiplier: f64,
     },
 
     #[serde(rename = "minecraft:quarter_negative")]
@@ -167,10 +191,313 @@ pub enum InlineDensityFunctionTree {
     BlendAlpha {},
 }
 
-#[derive(Deserialize)]
+/// A density function of a type added with [`custom::register`].
+#[derive(Deserialize, Serialize, Clone)]
+pub struct CustomDensityFunctionTree {
+    #[serde(rename = "type", deserialize_with = "custom::deserialize_kind")]
+    pub kind: Ident<String>,
+    #[serde(flatten)]
+    pub arguments: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize, Serialize, Copy, Clone)]
 pub enum RarityValueMapper {
     #[serde(rename = "type_1")]
     Type1,
     #[serde(rename = "type_2")]
     Type2,
 }
+
+impl DensityFunctionTree {
+    /// This tree with every reference replaced by the function it refers to
+    /// in `registry`, recursively. Arguments of custom types are kept as is.
+    pub fn inline_references<R: Registry + ?Sized>(&self, registry: &R) -> eyre::Result<Self> {
+        self.inline_with(registry, &mut vec![])
+    }
+
+    fn inline_with<R: Registry + ?Sized>(
+        &self,
+        registry: &R,
+        chain: &mut Vec<String>,
+    ) -> eyre::Result<Self> {
+        match self {
+            DensityFunctionTree::Constant(_) | DensityFunctionTree::Custom(_) => Ok(self.clone()),
+            DensityFunctionTree::Reference(id) => {
+                let id = Ident::new(id.as_str())?.to_string_ident();
+                if chain.iter().any(|r| r == id.as_str()) {
+                    return Err(eyre!(
+                        "density function reference cycle: {} -> {id}",
+                        chain.join(" -> ")
+                    ));
+                }
+
+                chain.push(id.to_string());
+                let result = registry
+                    .density_function(&id.as_str_ident())
+                    .and_then(|f| f.inline_with(registry, chain));
+                chain.pop();
+                result
+            }
+            DensityFunctionTree::Inline(f) => {
+                Ok(DensityFunctionTree::Inline(f.try_map_arguments(
+                    &mut |argument| Ok(Arc::new(argument.inline_with(registry, chain)?)),
+                )?))
+            }
+        }
+    }
+}
+
+impl InlineDensityFunctionTree {
+    /// This function with every argument replaced by `f` of it, including
+    /// spline coordinates.
+    pub(crate) fn try_map_arguments(
+        &self,
+        f: &mut impl FnMut(&Arc<DensityFunctionTree>) -> eyre::Result<Arc<DensityFunctionTree>>,
+    ) -> eyre::Result<Self> {
+        use InlineDensityFunctionTree as F;
+
+        Ok(match self {
+            F::Abs { argument } => F::Abs {
+                argument: f(argument)?,
+            },
+            F::Add {
+                argument1,
+                argument2,
+            } => F::Add {
+                argument1: f(argument1)?,
+                argument2: f(argument2)?,
+            },
+            F::BlendDensity { argument } => F::BlendDensity {
+                argument: f(argument)?,
+            },
+            F::Cache2D { argument } => F::Cache2D {
+                argument: f(argument)?,
+            },
+            F::CacheAllInCell { argument } => F::CacheAllInCell {
+                argument: f(argument)?,
+            },
+            F::CacheOnce { argument } => F::CacheOnce {
+                argument: f(argument)?,
+            },
+            F::FindTopSurface {
+                density,
+                upper_bound,
+                lower_bound,
+                cell_height,
+            } => F::FindTopSurface {
+                density: f(density)?,
+                upper_bound: f(upper_bound)?,
+                lower_bound: *lower_bound,
+                cell_height: *cell_height,
+            },
+            F::FlatCache { argument } => F::FlatCache {
+                argument: f(argument)?,
+            },
+            F::Clamp { input, min, max } => F::Clamp {
+                input: f(input)?,
+                min: *min,
+                max: *max,
+            },
+            F::Cube { argument } => F::Cube {
+                argument: f(argument)?,
+            },
+            F::HalfNegative { argument } => F::HalfNegative {
+                argument: f(argument)?,
+            },
+            F::Interpolated { argument } => F::Interpolated {
+                argument: f(argument)?,
+            },
+            F::Invert { argument } => F::Invert {
+                argument: f(argument)?,
+            },
+            F::Max {
+                argument1,
+                argument2,
+            } => F::Max {
+                argument1: f(argument1)?,
+                argument2: f(argument2)?,
+            },
+            F::Min {
+                argument1,
+                argument2,
+            } => F::Min {
+                argument1: f(argument1)?,
+                argument2: f(argument2)?,
+            },
+            F::Mul {
+                argument1,
+                argument2,
+            } => F::Mul {
+                argument1: f(argument1)?,
+                argument2: f(argument2)?,
+            },
+            F::QuarterNegative { argument } => F::QuarterNegative {
+                argument: f(argument)?,
+            },
+            F::RangeChoice {
+                input,
+                min_inclusive,
+                max_exclusive,
+                when_in_range,
+                when_out_of_range,
+            } => F::RangeChoice {
+                input: f(input)?,
+                min_inclusive: *min_inclusive,
+                max_exclusive: *max_exclusive,
+                when_in_range: f(when_in_range)?,
+                when_out_of_range: f(when_out_of_range)?,
+            },
+            F::ShiftedNoise {
+                noise,
+                xz_scale,
+                y_scale,
+                shift_x,
+                shift_y,
+                shift_z,
+            } => F::ShiftedNoise {
+                noise: noise.clone(),
+                xz_scale: *xz_scale,
+                y_scale: *y_scale,
+                shift_x: f(shift_x)?,
+                shift_y: f(shift_y)?,
+                shift_z: f(shift_z)?,
+            },
+            F::Slide { argument } => F::Slide {
+                argument: f(argument)?,
+            },
+            F::Spline { spline } => F::Spline {
+                spline: spline.try_map_coordinates(f)?,
+            },
+            F::Square { argument } => F::Square {
+                argument: f(argument)?,
+            },
+            F::Squeeze { argument } => F::Squeeze {
+                argument: f(argument)?,
+            },
+            F::WeirdScaledSampler {
+                rarity_value_mapper,
+                noise,
+                input,
+            } => F::WeirdScaledSampler {
+                rarity_value_mapper: *rarity_value_mapper,
+                noise: noise.clone(),
+                input: f(input)?,
+            },
+
+            F::Beardifier {}
+            | F::BlendAlpha {}
+            | F::BlendOffset {}
+            | F::Constant { .. }
+            | F::EndIslands {}
+            | F::Noise { .. }
+            | F::OldBlendNoise { .. }
+            | F::Shift { .. }
+            | F::ShiftA { .. }
+            | F::ShiftB { .. }
+            | F::YClampedGradient { .. } => self.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+    use valence_core::ident::Ident;
+
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::registry::memory::MemoryRegistry;
+
+    fn registry(functions: &[(&str, Value)]) -> MemoryRegistry {
+        let registry = MemoryRegistry::new();
+        for (id, json) in functions {
+            registry.insert_density_function(
+                &Ident::new(*id).unwrap().as_str_ident(),
+                serde_json::from_value::<DensityFunctionTree>(json.clone())
+                    .expect("should be a valid density function"),
+            );
+        }
+        registry
+    }
+
+    #[test]
+    fn serialize_test() {
+        let json = json!({
+            "type": "minecraft:add",
+            "argument1": {
+                "type": "minecraft:range_choice",
+                "input": "test:a",
+                "min_inclusive": -1.0,
+                "max_exclusive": 1.0,
+                "when_in_range": {"type": "minecraft:abs", "argument": 2.5},
+                "when_out_of_range": {"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": 1.0, "to_value": -1.0}
+            },
+            "argument2": {
+                "type": "minecraft:spline",
+                "spline": {
+                    "coordinate": "test:b",
+                    "points": [
+                        {"location": -1.0, "value": 0.5, "derivative": 0.0},
+                        {"location": 1.0, "value": {"coordinate": "test:a", "points": [{"location": 0.0, "value": 1.0, "derivative": 2.0}]}, "derivative": 0.0}
+                    ]
+                }
+            }
+        });
+
+        let tree: DensityFunctionTree = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(json, serde_json::to_value(&tree).unwrap());
+    }
+
+    #[test]
+    fn inline_references_test() {
+        let registry = registry(&[
+            (
+                "test:a",
+                json!({"type": "minecraft:abs", "argument": "test:b"}),
+            ),
+            ("test:b", json!(2.0)),
+            (
+                "test:c",
+                json!({"type": "minecraft:spline", "spline": {"coordinate": "test:a", "points": [{"location": 0.0, "value": 1.0, "derivative": 0.0}]}}),
+            ),
+        ]);
+
+        let tree: DensityFunctionTree = serde_json::from_value(
+            json!({"type": "minecraft:mul", "argument1": "test:a", "argument2": "test:c"}),
+        )
+        .unwrap();
+        assert_eq!(
+            json!({
+                "type": "minecraft:mul",
+                "argument1": {"type": "minecraft:abs", "argument": 2.0},
+                "argument2": {"type": "minecraft:spline", "spline": {
+                    "coordinate": {"type": "minecraft:abs", "argument": 2.0},
+                    "points": [{"location": 0.0, "value": 1.0, "derivative": 0.0}]
+                }}
+            }),
+            serde_json::to_value(tree.inline_references(&registry).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn inline_reference_cycle_test() {
+        let registry = registry(&[
+            (
+                "test:a",
+                json!({"type": "minecraft:abs", "argument": "test:b"}),
+            ),
+            (
+                "test:b",
+                json!({"type": "minecraft:add", "argument1": 1.0, "argument2": "test:a"}),
+            ),
+        ]);
+
+        let error = DensityFunctionTree::Reference("test:a".to_string())
+            .inline_references(&registry)
+            .err()
+            .expect("should detect the cycle");
+        assert_eq!(
+            "density function reference cycle: test:a -> test:b -> test:a",
+            error.to_string()
+        );
+    }
+}
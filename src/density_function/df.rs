@@ -0,0 +1,352 @@
+//! Builders for [`DensityFunctionTree`]s.
+//!
+//! ```ignore
+//! use valence_worldgen::density_function::df;
+//!
+//! let f = df::noise("minecraft:ridge", 1.0, 1.0).abs().mul(0.5)
+//!     + df::y_gradient(-64, 320, 1.0, -1.0);
+//! ```
+//!
+//! Functions taking a noise id panic if it isn't a valid identifier.
+
+use std::ops;
+use std::sync::Arc;
+
+use valence_core::ident::Ident;
+
+use crate::density_function::deserialize::{
+    DensityFunctionTree, InlineDensityFunctionTree, RarityValueMapper,
+};
+use crate::spline::{Blueprint, CubicSpline, CubicSplinePoint};
+
+fn noise_id(id: &str) -> Ident<String> {
+    Ident::new(id)
+        .unwrap_or_else(|e| panic!("invalid noise id {id:?}: {e}"))
+        .to_string_ident()
+}
+
+fn inline(f: InlineDensityFunctionTree) -> DensityFunctionTree {
+    DensityFunctionTree::Inline(f)
+}
+
+fn arc(f: impl Into<DensityFunctionTree>) -> Arc<DensityFunctionTree> {
+    Arc::new(f.into())
+}
+
+pub fn constant(value: f64) -> DensityFunctionTree {
+    DensityFunctionTree::Constant(value)
+}
+
+/// The registered density function `id`, resolved when compiling.
+pub fn reference(id: &str) -> DensityFunctionTree {
+    DensityFunctionTree::Reference(id.to_string())
+}
+
+pub fn noise(id: &str, xz_scale: f64, y_scale: f64) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::Noise {
+        noise: noise_id(id),
+        xz_scale,
+        y_scale,
+    })
+}
+
+pub fn shifted_noise(
+    id: &str,
+    xz_scale: f64,
+    y_scale: f64,
+    shift_x: impl Into<DensityFunctionTree>,
+    shift_y: impl Into<DensityFunctionTree>,
+    shift_z: impl Into<DensityFunctionTree>,
+) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::ShiftedNoise {
+        noise: noise_id(id),
+        xz_scale,
+        y_scale,
+        shift_x: arc(shift_x),
+        shift_y: arc(shift_y),
+        shift_z: arc(shift_z),
+    })
+}
+
+pub fn shift(id: &str) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::Shift {
+        noise: noise_id(id),
+    })
+}
+
+pub fn shift_a(id: &str) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::ShiftA {
+        noise: noise_id(id),
+    })
+}
+
+pub fn shift_b(id: &str) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::ShiftB {
+        noise: noise_id(id),
+    })
+}
+
+pub fn weird_scaled_sampler(
+    input: impl Into<DensityFunctionTree>,
+    id: &str,
+    rarity_value_mapper: RarityValueMapper,
+) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::WeirdScaledSampler {
+        rarity_value_mapper,
+        noise: noise_id(id),
+        input: arc(input),
+    })
+}
+
+pub fn old_blended_noise(
+    xz_scale: f64,
+    y_scale: f64,
+    xz_factor: f64,
+    y_factor: f64,
+    smear_scale_multiplier: f64,
+) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::OldBlendNoise {
+        xz_scale,
+        y_scale,
+        xz_factor,
+        y_factor,
+        smear_scale_multiplier,
+    })
+}
+
+/// `from_value` at `from_y` and below, `to_value` at `to_y` and above.
+pub fn y_gradient(from_y: i32, to_y: i32, from_value: f64, to_value: f64) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::YClampedGradient {
+        from_y,
+        to_y,
+        from_value,
+        to_value,
+    })
+}
+
+pub fn range_choice(
+    input: impl Into<DensityFunctionTree>,
+    min_inclusive: f64,
+    max_exclusive: f64,
+    when_in_range: impl Into<DensityFunctionTree>,
+    when_out_of_range: impl Into<DensityFunctionTree>,
+) -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::RangeChoice {
+        input: arc(input),
+        min_inclusive,
+        max_exclusive,
+        when_in_range: arc(when_in_range),
+        when_out_of_range: arc(when_out_of_range),
+    })
+}
+
+pub fn beardifier() -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::Beardifier {})
+}
+
+pub fn blend_alpha() -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::BlendAlpha {})
+}
+
+pub fn blend_offset() -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::BlendOffset {})
+}
+
+pub fn end_islands() -> DensityFunctionTree {
+    inline(InlineDensityFunctionTree::EndIslands {})
+}
+
+/// A spline over `coordinate`, add its points with [`SplineBuilder::point`].
+pub fn spline(coordinate: impl Into<DensityFunctionTree>) -> SplineBuilder {
+    SplineBuilder {
+        coordinate: arc(coordinate),
+        points: vec![],
+    }
+}
+
+pub struct SplineBuilder {
+    coordinate: Arc<DensityFunctionTree>,
+    points: Vec<CubicSplinePoint<Blueprint>>,
+}
+
+impl SplineBuilder {
+    /// Adds a point, `value` is either a constant or a nested spline.
+    /// Points have to be added in ascending `location` order.
+    pub fn point(
+        mut self,
+        location: f32,
+        value: impl Into<CubicSpline<Blueprint>>,
+        derivative: f32,
+    ) -> Self {
+        self.points
+            .push(CubicSplinePoint::new(location, value.into(), derivative));
+        self
+    }
+}
+
+impl From<SplineBuilder> for CubicSpline<Blueprint> {
+    fn from(spline: SplineBuilder) -> Self {
+        CubicSpline::MultipointBlueprint {
+            coordinate: spline.coordinate,
+            points: spline.points,
+        }
+    }
+}
+
+impl From<SplineBuilder> for DensityFunctionTree {
+    fn from(spline: SplineBuilder) -> Self {
+        inline(InlineDensityFunctionTree::Spline {
+            spline: spline.into(),
+        })
+    }
+}
+
+impl From<f32> for CubicSpline<Blueprint> {
+    fn from(value: f32) -> Self {
+        CubicSpline::Constant(value)
+    }
+}
+
+impl From<f64> for DensityFunctionTree {
+    fn from(value: f64) -> Self {
+        DensityFunctionTree::Constant(value)
+    }
+}
+
+impl From<InlineDensityFunctionTree> for DensityFunctionTree {
+    fn from(f: InlineDensityFunctionTree) -> Self {
+        inline(f)
+    }
+}
+
+macro_rules! unary {
+    ($($(#[$doc:meta])* $name:ident => $variant:ident,)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(self) -> DensityFunctionTree {
+                inline(InlineDensityFunctionTree::$variant {
+                    argument: Arc::new(self),
+                })
+            }
+        )*
+    };
+}
+
+macro_rules! binary {
+    ($($name:ident => $variant:ident,)*) => {
+        $(
+            // Also callable without importing the `ops` traits.
+            #[allow(clippy::should_implement_trait)]
+            pub fn $name(self, other: impl Into<DensityFunctionTree>) -> DensityFunctionTree {
+                inline(InlineDensityFunctionTree::$variant {
+                    argument1: Arc::new(self),
+                    argument2: arc(other),
+                })
+            }
+        )*
+    };
+}
+
+impl DensityFunctionTree {
+    unary! {
+        abs => Abs,
+        square => Square,
+        cube => Cube,
+        half_negative => HalfNegative,
+        quarter_negative => QuarterNegative,
+        squeeze => Squeeze,
+        invert => Invert,
+        blend_density => BlendDensity,
+        /// Interpolated between the corners of the noise cell.
+        interpolated => Interpolated,
+        flat_cache => FlatCache,
+        cache_2d => Cache2D,
+        cache_once => CacheOnce,
+        cache_all_in_cell => CacheAllInCell,
+    }
+
+    binary! {
+        add => Add,
+        mul => Mul,
+        min => Min,
+        max => Max,
+    }
+
+    pub fn clamp(self, min: f64, max: f64) -> DensityFunctionTree {
+        inline(InlineDensityFunctionTree::Clamp {
+            input: Arc::new(self),
+            min,
+            max,
+        })
+    }
+}
+
+impl<T: Into<DensityFunctionTree>> ops::Add<T> for DensityFunctionTree {
+    type Output = DensityFunctionTree;
+
+    fn add(self, rhs: T) -> Self::Output {
+        DensityFunctionTree::add(self, rhs)
+    }
+}
+
+impl<T: Into<DensityFunctionTree>> ops::Mul<T> for DensityFunctionTree {
+    type Output = DensityFunctionTree;
+
+    fn mul(self, rhs: T) -> Self::Output {
+        DensityFunctionTree::mul(self, rhs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::density_function::df;
+
+    #[test]
+    fn builder_test() {
+        let f = df::noise("minecraft:ridge", 1.0, 0.5).abs().mul(0.5)
+            + df::y_gradient(-64, 320, 1.0, -1.0) * df::reference("minecraft:zero");
+        assert_eq!(
+            json!({
+                "type": "minecraft:add",
+                "argument1": {
+                    "type": "minecraft:mul",
+                    "argument1": {
+                        "type": "minecraft:abs",
+                        "argument": {"type": "minecraft:noise", "noise": "minecraft:ridge", "xz_scale": 1.0, "y_scale": 0.5}
+                    },
+                    "argument2": 0.5
+                },
+                "argument2": {
+                    "type": "minecraft:mul",
+                    "argument1": {"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": 1.0, "to_value": -1.0},
+                    "argument2": "minecraft:zero"
+                }
+            }),
+            serde_json::to_value(f).unwrap()
+        );
+    }
+
+    #[test]
+    fn spline_builder_test() {
+        let f: DensityFunctionTree = df::spline(df::reference("minecraft:a"))
+            .point(-1.0, 0.5, 0.0)
+            .point(1.0, df::spline(df::constant(2.0)).point(0.0, 1.0, 2.0), 0.0)
+            .into();
+        assert_eq!(
+            json!({
+                "type": "minecraft:spline",
+                "spline": {
+                    "coordinate": "minecraft:a",
+                    "points": [
+                        {"location": -1.0, "value": 0.5, "derivative": 0.0},
+                        {"location": 1.0, "value": {"coordinate": 2.0, "points": [{"location": 0.0, "value": 1.0, "derivative": 2.0}]}, "derivative": 0.0}
+                    ]
+                }
+            }),
+            serde_json::to_value(f).unwrap()
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use valence_core::block_pos::BlockPos;
+
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction};
+use crate::noise::simplex::SimplexNoise;
+use crate::random::legacy::LegacyRandom;
+
+pub struct EndIslands {
+    island_noise: SimplexNoise,
+}
+
+impl EndIslands {
+    pub fn new(seed: i64) -> Box<dyn DensityFunction> {
+        let mut r = LegacyRandom::new(seed);
+        r.consume(17292);
+
+        Box::new(Self {
+            island_noise: SimplexNoise::new(r.as_mut()),
+        })
+    }
+
+    fn height_value(&self, x: i32, z: i32) -> f32 {
+        let (chunk_x, chunk_z) = (x / 2, z / 2);
+        let (offset_x, offset_z) = (x % 2, z % 2);
+
+        // vanilla's int arithmetic wraps far out, the square root of a
+        // negative distance is NaN, which its clamp and max keep
+        let distance = x.wrapping_mul(x).wrapping_add(z.wrapping_mul(z));
+        let mut height = (100.0 - (distance as f32).sqrt() * 8.0).clamp(-100.0, 80.0);
+
+        for dx in -12..=12 {
+            for dz in -12..=12 {
+                let island_x = (chunk_x + dx) as i64;
+                let island_z = (chunk_z + dz) as i64;
+
+                if island_x * island_x + island_z * island_z > 4096
+                    && self
+                        .island_noise
+                        .get_value_2d(island_x as f64, island_z as f64)
+                        < -0.9_f32 as f64
+                {
+                    let falloff =
+                        ((island_x as f32).abs() * 3439.0 + (island_z as f32).abs() * 147.0) % 13.0
+                            + 9.0;
+                    let h = (offset_x - dx * 2) as f32;
+                    let v = (offset_z - dz * 2) as f32;
+                    let island_height =
+                        (100.0 - (h * h + v * v).sqrt() * falloff).clamp(-100.0, 80.0);
+                    if !height.is_nan() {
+                        height = height.max(island_height);
+                    }
+                }
+            }
+        }
+
+        height
+    }
+}
+
+impl DensityFunction for EndIslands {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        (self.height_value(pos.x / 8, pos.z / 8) as f64 - 8.0) / 128.0
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        context_provider.fill_direct(slice, self)
+    }
+
+    fn min(&self) -> f64 {
+        -0.84375
+    }
+
+    fn max(&self) -> f64 {
+        0.5625
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("end_islands")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use valence_core::block_pos::BlockPos;
+
+    use crate::density_function::end_islands::EndIslands;
+
+    #[test]
+    fn end_islands_bounds_test() {
+        let f = EndIslands::new(0x786b544d6f473757_i64);
+
+        assert_eq!(0.5625, f.compute(BlockPos::new(0, 64, 0)));
+        assert_eq!(-0.84375, f.compute(BlockPos::new(800, 64, 0)));
+
+        for x in (-20000..20000).step_by(997) {
+            for z in (-20000..20000).step_by(991) {
+                let v = f.compute(BlockPos::new(x, 0, z));
+                assert!(
+                    v >= f.min() && v <= f.max(),
+                    "{v} out of bounds at {x}, {z}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn end_islands_far_out_test() {
+        let f = EndIslands::new(0x786b544d6f473757_i64);
+
+        let v = f.compute(BlockPos::new(30_000_000, 64, 0));
+        assert!(v >= f.min() && v <= f.max(), "{v} out of bounds");
+        // 625000 * 625000 wraps to a negative int
+        assert!(f.compute(BlockPos::new(5_000_000, 64, 0)).is_nan());
+    }
+}
@@ -0,0 +1,70 @@
+use valence_core::block_pos::BlockPos;
+
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction};
+
+/// Scans downwards from `upper_bound` in steps of `cell_height` and yields
+/// the first y level where `density` is positive.
+pub struct FindTopSurface {
+    density: Box<dyn DensityFunction>,
+    upper_bound: Box<dyn DensityFunction>,
+    lower_bound: i32,
+    cell_height: i32,
+}
+
+impl FindTopSurface {
+    pub fn new(
+        density: Box<dyn DensityFunction>,
+        upper_bound: Box<dyn DensityFunction>,
+        lower_bound: i32,
+        cell_height: i32,
+    ) -> eyre::Result<Box<dyn DensityFunction>> {
+        if cell_height <= 0 {
+            return Err(eyre::eyre!(
+                "cell_height must be positive, got {cell_height}"
+            ));
+        }
+
+        Ok(Box::new(Self {
+            density,
+            upper_bound,
+            lower_bound,
+            cell_height,
+        }))
+    }
+}
+
+impl DensityFunction for FindTopSurface {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        let top = (self.upper_bound.compute(pos) / self.cell_height as f64).floor() as i32
+            * self.cell_height;
+
+        if top <= self.lower_bound {
+            return self.lower_bound as f64;
+        }
+
+        (self.lower_bound..=top)
+            .rev()
+            .step_by(self.cell_height as usize)
+            .find(|&y| self.density.compute(BlockPos::new(pos.x, y, pos.z)) > 0.0)
+            .unwrap_or(self.lower_bound) as f64
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        context_provider.fill_direct(slice, self)
+    }
+
+    fn min(&self) -> f64 {
+        self.lower_bound as f64
+    }
+
+    fn max(&self) -> f64 {
+        f64::max(self.lower_bound as f64, self.upper_bound.max())
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("find_top_surface")
+            .with_argument(self.density.as_ref())
+            .with_argument(self.upper_bound.as_ref())
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::iter::zip;
+
+use valence_core::block_pos::BlockPos;
+
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction, PositionsContextProvider};
+
+/// Trilinear interpolation of the argument between the corners of the noise
+/// cell containing the position.
+pub struct Interpolated {
+    f: Box<dyn DensityFunction>,
+    cell_width: i32,
+    cell_height: i32,
+}
+
+impl Interpolated {
+    pub fn new(
+        f: Box<dyn DensityFunction>,
+        cell_width: i32,
+        cell_height: i32,
+    ) -> Box<dyn DensityFunction> {
+        Box::new(Self {
+            f,
+            cell_width,
+            cell_height,
+        })
+    }
+}
+
+impl Interpolated {
+    /// The corner of the cell containing `pos` with the lowest coordinates
+    /// and the position within the cell, in fractions of the cell size.
+    fn cell(&self, pos: BlockPos) -> ((i32, i32, i32), (f64, f64, f64)) {
+        let (w, h) = (self.cell_width, self.cell_height);
+        let x0 = pos.x.div_euclid(w) * w;
+        let y0 = pos.y.div_euclid(h) * h;
+        let z0 = pos.z.div_euclid(w) * w;
+
+        let dx = (pos.x - x0) as f64 / w as f64;
+        let dy = (pos.y - y0) as f64 / h as f64;
+        let dz = (pos.z - z0) as f64 / w as f64;
+
+        ((x0, y0, z0), (dx, dy, dz))
+    }
+
+    /// The position of the corner `i` of the cell at `origin`, bit 2 of `i`
+    /// is x, bit 1 is y and bit 0 is z.
+    fn corner(&self, (x0, y0, z0): (i32, i32, i32), i: usize) -> BlockPos {
+        let (w, h) = (self.cell_width, self.cell_height);
+        BlockPos::new(
+            x0 + (i >> 2) as i32 * w,
+            y0 + (i >> 1 & 1) as i32 * h,
+            z0 + (i & 1) as i32 * w,
+        )
+    }
+}
+
+impl DensityFunction for Interpolated {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        let (origin, delta) = self.cell(pos);
+        let corners = std::array::from_fn(|i| self.f.compute(self.corner(origin, i)));
+        interpolate(delta, corners)
+    }
+
+    /// Fills the corners of every cell in the slice once, in one batch, and
+    /// interpolates the positions in between.
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        let mut corner_of = HashMap::new();
+        let mut corners = vec![];
+        let cells = (0..slice.len())
+            .map(|i| {
+                let (origin, delta) = self.cell(context_provider.for_index(i));
+                let indices: [usize; 8] = std::array::from_fn(|i| {
+                    let corner = self.corner(origin, i);
+                    *corner_of
+                        .entry((corner.x, corner.y, corner.z))
+                        .or_insert_with(|| {
+                            corners.push(corner);
+                            corners.len() - 1
+                        })
+                });
+                (indices, delta)
+            })
+            .collect::<Vec<_>>();
+
+        let mut values = vec![0.0; corners.len()];
+        self.f
+            .fill(&mut values, &PositionsContextProvider::new(&corners));
+
+        for (v, (indices, delta)) in zip(slice, cells) {
+            *v = interpolate(delta, indices.map(|i| values[i]));
+        }
+    }
+
+    fn min(&self) -> f64 {
+        self.f.min()
+    }
+
+    fn max(&self) -> f64 {
+        self.f.max()
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("interpolated").with_argument(self.f.as_ref())
+    }
+}
+
+/// Trilinear interpolation between the `corners` of a cell, indexed like
+/// [`Interpolated::corner`].
+fn interpolate((dx, dy, dz): (f64, f64, f64), corners: [f64; 8]) -> f64 {
+    // same order as vanilla: along y first, then x, then z
+    let v00 = lerp(dy, corners[0b000], corners[0b010]);
+    let v10 = lerp(dy, corners[0b100], corners[0b110]);
+    let v01 = lerp(dy, corners[0b001], corners[0b011]);
+    let v11 = lerp(dy, corners[0b101], corners[0b111]);
+
+    let v0 = lerp(dx, v00, v10);
+    let v1 = lerp(dx, v01, v11);
+
+    lerp(dz, v0, v1)
+}
+
+fn lerp(t: f64, u0: f64, u1: f64) -> f64 {
+    u0 + t * (u1 - u0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Interpolated;
+    use crate::density_function::test::{Column, Counting};
+    use crate::density_function::y_clamped_gradient::YClampedGradient;
+    use crate::density_function::ContextProvider;
+
+    #[test]
+    fn interpolated_fill_test() {
+        let f = Counting::new(YClampedGradient::new(-64, 320, -3.0, 5.0).unwrap());
+        let interpolated = Interpolated::new(Box::new(f.clone()), 4, 8);
+
+        let mut slice = [0.0; 384];
+        interpolated.fill(&mut slice, &Column);
+        // the corners of the 48 cells of the column are filled once
+        assert_eq!(1, f.fills());
+        assert_eq!(49 * 4, f.calls());
+
+        for (i, v) in slice.into_iter().enumerate() {
+            let pos = Column.for_index(i);
+            assert_eq!(
+                interpolated.compute(pos).to_bits(),
+                v.to_bits(),
+                "at {pos:?}"
+            );
+        }
+    }
+}
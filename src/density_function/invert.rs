@@ -0,0 +1,6 @@
+use crate::density_function::transformer::Mapper;
+use crate::density_function::DensityFunction;
+
+pub fn invert(f: Box<dyn DensityFunction>) -> Box<dyn DensityFunction> {
+    Mapper::Invert.map(f)
+}
@@ -1,10 +1,15 @@
+use std::sync::Arc;
+
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::describe::Description;
+
 #[cfg(test)]
 mod test;
 
 mod abs;
 mod add;
+pub mod beardifier;
 mod cache_2d;
 mod cache_once;
 mod clamp;
@@ -12,19 +17,31 @@ mod commutative;
 pub mod compile;
 mod constant;
 mod cube;
+pub mod custom;
+pub mod describe;
 pub mod deserialize;
+pub mod df;
+mod end_islands;
+mod find_top_surface;
 mod flat_cache;
 mod half_negative;
+mod interpolated;
+mod invert;
 mod max;
 mod min;
 mod mul;
 mod noise;
+mod old_blended_noise;
 mod quarter_negative;
 mod range_choice;
+mod reference;
+mod simplify;
 mod spline;
 mod square;
 mod squeeze;
+mod tape;
 mod transformer;
+mod weird_scaled_sampler;
 pub(crate) mod y_clamped_gradient;
 
 pub trait DensityFunction: Send + Sync {
@@ -32,6 +49,45 @@ pub trait DensityFunction: Send + Sync {
     fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider);
     fn min(&self) -> f64;
     fn max(&self) -> f64;
+
+    /// The value of this function if it does not depend on the position.
+    fn as_constant(&self) -> Option<f64> {
+        None
+    }
+
+    /// What this node is and its arguments, to walk the compiled function.
+    /// See [`describe::to_text`] and [`describe::to_dot`] for whole dumps.
+    fn describe(&self) -> Description<'_> {
+        Description::new(std::any::type_name::<Self>())
+    }
+}
+
+/// A compiled function shared by several parents, e.g. every reference to
+/// the same id.
+impl<T: DensityFunction + ?Sized> DensityFunction for Arc<T> {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        self.as_ref().compute(pos)
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        self.as_ref().fill(slice, context_provider)
+    }
+
+    fn min(&self) -> f64 {
+        self.as_ref().min()
+    }
+
+    fn max(&self) -> f64 {
+        self.as_ref().max()
+    }
+
+    fn as_constant(&self) -> Option<f64> {
+        self.as_ref().as_constant()
+    }
+
+    fn describe(&self) -> Description<'_> {
+        self.as_ref().describe()
+    }
 }
 
 fn sort_min_max(min: f64, max: f64) -> (f64, f64) {
@@ -46,3 +102,52 @@ pub trait ContextProvider {
     fn for_index(&self, idx: usize) -> BlockPos;
     fn fill_direct(&self, slice: &mut [f64], filler: &dyn DensityFunction);
 }
+
+/// The positions at `indices` of `parent`, in order, so a function can be
+/// filled over a subset of the slice of `parent` only, still in one batch.
+pub(crate) struct SubsetContextProvider<'a> {
+    parent: &'a dyn ContextProvider,
+    indices: &'a [usize],
+}
+
+impl<'a> SubsetContextProvider<'a> {
+    pub(crate) fn new(parent: &'a dyn ContextProvider, indices: &'a [usize]) -> Self {
+        Self { parent, indices }
+    }
+}
+
+impl ContextProvider for SubsetContextProvider<'_> {
+    fn for_index(&self, idx: usize) -> BlockPos {
+        self.parent.for_index(self.indices[idx])
+    }
+
+    fn fill_direct(&self, slice: &mut [f64], filler: &dyn DensityFunction) {
+        for (i, v) in slice.iter_mut().enumerate() {
+            *v = filler.compute(self.for_index(i));
+        }
+    }
+}
+
+/// A list of arbitrary positions, e.g. the corners of noise cells or a column
+/// of blocks.
+pub(crate) struct PositionsContextProvider<'a> {
+    positions: &'a [BlockPos],
+}
+
+impl<'a> PositionsContextProvider<'a> {
+    pub(crate) fn new(positions: &'a [BlockPos]) -> Self {
+        Self { positions }
+    }
+}
+
+impl ContextProvider for PositionsContextProvider<'_> {
+    fn for_index(&self, idx: usize) -> BlockPos {
+        self.positions[idx]
+    }
+
+    fn fill_direct(&self, slice: &mut [f64], filler: &dyn DensityFunction) {
+        for (v, &pos) in slice.iter_mut().zip(self.positions) {
+            *v = filler.compute(pos);
+        }
+    }
+}
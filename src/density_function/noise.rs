@@ -1,9 +1,11 @@
+use std::iter::zip;
 use std::simd::{f64x4, i32x4};
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError};
 
 use valence_core::block_pos::BlockPos;
 use valence_core::ident::Ident;
 
+use crate::density_function::describe::Description;
 use crate::density_function::{ContextProvider, DensityFunction};
 use crate::noise::normal::NormalNoise;
 use crate::random::random_state::RandomState;
@@ -15,6 +17,7 @@ const DEFAULT_SCRAMBLER: InputScrambler =
 
 #[derive(Clone)]
 pub struct Noise {
+    id: Ident<String>,
     noise: Arc<NormalNoise>,
     value_factor: f64,
     input_factor: f64x4,
@@ -33,15 +36,17 @@ enum Shift {
 
 impl Noise {
     pub fn new(
-        noise: NormalNoise,
+        id: Ident<String>,
+        noise: Arc<NormalNoise>,
         value_factor: f64,
         input_factor: f64x4,
     ) -> Box<dyn DensityFunction> {
-        Self::new_with_scrambler(noise, value_factor, input_factor, DEFAULT_SCRAMBLER)
+        Self::new_with_scrambler(id, noise, value_factor, input_factor, DEFAULT_SCRAMBLER)
     }
 
     pub fn new_with_shift(
-        noise: NormalNoise,
+        id: Ident<String>,
+        noise: Arc<NormalNoise>,
         value_factor: f64,
         input_factor: f64x4,
         shift_x: Box<dyn DensityFunction>,
@@ -49,7 +54,8 @@ impl Noise {
         shift_z: Box<dyn DensityFunction>,
     ) -> Box<dyn DensityFunction> {
         Box::new(Self {
-            noise: Arc::new(noise),
+            id,
+            noise,
             value_factor,
             input_factor,
             input_scrambler: DEFAULT_SCRAMBLER,
@@ -62,13 +68,15 @@ impl Noise {
     }
 
     pub fn new_with_scrambler(
-        noise: NormalNoise,
+        id: Ident<String>,
+        noise: Arc<NormalNoise>,
         value_factor: f64,
         input_factor: f64x4,
         input_scrambler: InputScrambler,
     ) -> Box<dyn DensityFunction> {
         Box::new(Self {
-            noise: Arc::new(noise),
+            id,
+            noise,
             value_factor,
             input_factor,
             input_scrambler,
@@ -92,7 +100,20 @@ impl DensityFunction for Noise {
     }
 
     fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
-        context_provider.fill_direct(slice, self)
+        let mut input = (0..slice.len())
+            .map(|i| (self.input_scrambler)(context_provider.for_index(i)) * self.input_factor)
+            .collect::<Vec<_>>();
+
+        if let Shift::Dynamic { x, y, z } = self.shift.as_ref() {
+            let mut shift = vec![0.0; slice.len()];
+            for (axis, f) in [x, y, z].into_iter().enumerate() {
+                f.fill(&mut shift, context_provider);
+                zip(&mut input, &shift).for_each(|(input, s)| input[axis] += s);
+            }
+        }
+
+        self.noise.get_values(&input, slice);
+        slice.iter_mut().for_each(|v| *v *= self.value_factor)
     }
 
     fn min(&self) -> f64 {
@@ -102,6 +123,17 @@ impl DensityFunction for Noise {
     fn max(&self) -> f64 {
         self.noise.max() * self.value_factor
     }
+
+    fn describe(&self) -> Description<'_> {
+        match self.shift.as_ref() {
+            Shift::None => Description::new("noise").with_noise(&self.id),
+            Shift::Dynamic { x, y, z } => Description::new("shifted_noise")
+                .with_noise(&self.id)
+                .with_argument(x.as_ref())
+                .with_argument(y.as_ref())
+                .with_argument(z.as_ref()),
+        }
+    }
 }
 
 pub fn noise(
@@ -111,7 +143,12 @@ pub fn noise(
     input_factor: f64x4,
 ) -> eyre::Result<Box<dyn DensityFunction>> {
     let noise = instantiate_noise(id, random_state)?;
-    Ok(Noise::new(noise, value_factor, input_factor))
+    Ok(Noise::new(
+        id.to_string_ident(),
+        noise,
+        value_factor,
+        input_factor,
+    ))
 }
 
 pub fn shift_noise(
@@ -123,6 +160,7 @@ pub fn shift_noise(
 ) -> eyre::Result<Box<dyn DensityFunction>> {
     let noise = instantiate_noise(id, random_state)?;
     Ok(Noise::new_with_scrambler(
+        id.to_string_ident(),
         noise,
         value_factor,
         input_factor,
@@ -141,6 +179,7 @@ pub fn shifted_noise(
 ) -> eyre::Result<Box<dyn DensityFunction>> {
     let noise = instantiate_noise(id, random_state)?;
     Ok(Noise::new_with_shift(
+        id.to_string_ident(),
         noise,
         value_factor,
         input_factor,
@@ -150,11 +189,83 @@ pub fn shifted_noise(
     ))
 }
 
-fn instantiate_noise(id: &Ident<&str>, random_state: &RandomState) -> eyre::Result<NormalNoise> {
+/// The noise `id` of `random_state`, instantiated once per random state.
+pub(crate) fn instantiate_noise(
+    id: &Ident<&str>,
+    random_state: &RandomState,
+) -> eyre::Result<Arc<NormalNoise>> {
+    let cache = &random_state.noise_instance_cache;
+    if let Some(noise) = cache
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&id.to_string_ident())
+    {
+        return Ok(noise.clone());
+    }
+
     let noise_data = random_state.registry.noise(id)?;
     let noise = NormalNoise::new(
         random_state.random.with_hash_of(id.as_str()).as_mut(),
         &noise_data,
-    );
-    Ok(noise)
+    )?;
+    Ok(cache
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry(id.to_string_ident())
+        .or_insert_with(|| Arc::new(noise))
+        .clone())
+}
+
+#[cfg(test)]
+mod test {
+    use std::simd::f64x4;
+    use std::sync::Arc;
+
+    use valence_core::ident;
+
+    use crate::density_function::noise::Noise;
+    use crate::density_function::test::{Column, Counting};
+    use crate::density_function::y_clamped_gradient::YClampedGradient;
+    use crate::density_function::{ContextProvider, DensityFunction};
+    use crate::noise::deserialize::NoiseParameters;
+    use crate::noise::normal::NormalNoise;
+    use crate::random::xoroshiro::XoroshiroRandom;
+
+    #[test]
+    fn noise_fill_test() {
+        let noise = Arc::new(
+            NormalNoise::new(
+                XoroshiroRandom::new(42).as_mut(),
+                &NoiseParameters::new(-4, vec![1.0, 0.5, 0.0, 1.0]),
+            )
+            .unwrap(),
+        );
+        let id = ident!("test:noise").to_string_ident();
+        let input_factor = f64x4::from_array([0.25, 0.5, 0.25, 0.0]);
+        let shift = |from_value, to_value| -> Box<dyn DensityFunction> {
+            Box::new(Counting::new(
+                YClampedGradient::new(-64, 320, from_value, to_value).unwrap(),
+            ))
+        };
+
+        for f in [
+            Noise::new(id.clone(), noise.clone(), 1.5, input_factor),
+            Noise::new_with_shift(
+                id.clone(),
+                noise.clone(),
+                -0.5,
+                input_factor,
+                shift(-100.0, 100.0),
+                shift(3.0, 7.0),
+                shift(0.0, -1000.0),
+            ),
+        ] {
+            let mut slice = [0.0; 383];
+            f.fill(&mut slice, &Column);
+            for (i, v) in slice.into_iter().enumerate() {
+                let pos = Column.for_index(i);
+                assert_eq!(f.compute(pos).to_bits(), v.to_bits(), "at {pos:?}");
+            }
+        }
+    }
 }
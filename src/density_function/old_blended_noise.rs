@@ -0,0 +1,27 @@
+use valence_core::block_pos::BlockPos;
+
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction};
+use crate::noise::blended::BlendedNoise;
+
+impl DensityFunction for BlendedNoise {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        self.get_value(pos.x, pos.y, pos.z)
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        context_provider.fill_direct(slice, self)
+    }
+
+    fn min(&self) -> f64 {
+        -self.max()
+    }
+
+    fn max(&self) -> f64 {
+        self.max()
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("old_blended_noise")
+    }
+}
@@ -1,6 +1,9 @@
+use std::iter::zip;
+
 use valence_core::block_pos::BlockPos;
 
-use crate::density_function::{ContextProvider, DensityFunction};
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction, SubsetContextProvider};
 
 pub struct RangeChoice {
     input: Box<dyn DensityFunction>,
@@ -26,13 +29,15 @@ impl RangeChoice {
             when_out_of_range,
         })
     }
+
+    fn in_range(&self, choice: f64) -> bool {
+        choice >= self.min_inclusive && choice < self.max_exclusive
+    }
 }
 
 impl DensityFunction for RangeChoice {
     fn compute(&self, pos: BlockPos) -> f64 {
-        let choice = self.input.compute(pos);
-
-        if choice >= self.min_inclusive && choice < self.max_exclusive {
+        if self.in_range(self.input.compute(pos)) {
             self.when_in_range.compute(pos)
         } else {
             self.when_out_of_range.compute(pos)
@@ -42,15 +47,24 @@ impl DensityFunction for RangeChoice {
     fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
         self.input.fill(slice, context_provider);
 
-        slice.iter_mut().enumerate().for_each(|(i, v)| {
-            if *v >= self.min_inclusive && *v < self.max_exclusive {
-                *v = self.when_in_range.compute(context_provider.for_index(i))
-            } else {
-                *v = self
-                    .when_out_of_range
-                    .compute(context_provider.for_index(i))
+        let (in_range, out_of_range): (Vec<_>, Vec<_>) =
+            (0..slice.len()).partition(|&i| self.in_range(slice[i]));
+
+        for (indices, f) in [
+            (in_range, &self.when_in_range),
+            (out_of_range, &self.when_out_of_range),
+        ] {
+            if indices.len() == slice.len() {
+                f.fill(slice, context_provider);
+            } else if !indices.is_empty() {
+                let mut values = vec![0.0; indices.len()];
+                f.fill(
+                    &mut values,
+                    &SubsetContextProvider::new(context_provider, &indices),
+                );
+                zip(indices, values).for_each(|(i, v)| slice[i] = v);
             }
-        })
+        }
     }
 
     fn min(&self) -> f64 {
@@ -60,4 +74,45 @@ impl DensityFunction for RangeChoice {
     fn max(&self) -> f64 {
         f64::max(self.when_in_range.max(), self.when_out_of_range.max())
     }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("range_choice")
+            .with_argument(self.input.as_ref())
+            .with_argument(self.when_in_range.as_ref())
+            .with_argument(self.when_out_of_range.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::density_function::range_choice::RangeChoice;
+    use crate::density_function::test::{Column, Counting};
+    use crate::density_function::y_clamped_gradient::YClampedGradient;
+    use crate::density_function::ContextProvider;
+
+    #[test]
+    fn range_choice_fill_test() {
+        let in_range = Counting::new(YClampedGradient::new(-64, 320, -1.0, 1.0).unwrap());
+        let out_of_range = Counting::new(YClampedGradient::new(-64, 320, 3.0, 2.0).unwrap());
+        // in range for y in 0..128
+        let f = RangeChoice::new(
+            Box::new(Counting::new(
+                YClampedGradient::new(-64, 320, -1.0, 5.0).unwrap(),
+            )),
+            0.0,
+            2.0,
+            Box::new(in_range.clone()),
+            Box::new(out_of_range.clone()),
+        );
+
+        let mut slice = [0.0; 384];
+        f.fill(&mut slice, &Column);
+        assert_eq!((1, 128), (in_range.fills(), in_range.calls()));
+        assert_eq!((1, 256), (out_of_range.fills(), out_of_range.calls()));
+
+        for (i, v) in slice.into_iter().enumerate() {
+            let pos = Column.for_index(i);
+            assert_eq!(f.compute(pos), v, "at {pos:?}");
+        }
+    }
 }
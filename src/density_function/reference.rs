@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+use valence_core::ident::Ident;
+
+use crate::density_function::describe::Description;
+use crate::density_function::{ContextProvider, DensityFunction};
+
+/// A compiled reference to `id`, sharing the function with every other
+/// reference to it.
+pub struct Reference {
+    id: Ident<String>,
+    f: Arc<dyn DensityFunction>,
+}
+
+impl Reference {
+    pub fn new(id: Ident<String>, f: Arc<dyn DensityFunction>) -> Box<dyn DensityFunction> {
+        Box::new(Reference { id, f })
+    }
+}
+
+impl DensityFunction for Reference {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        self.f.compute(pos)
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        self.f.fill(slice, context_provider)
+    }
+
+    fn min(&self) -> f64 {
+        self.f.min()
+    }
+
+    fn max(&self) -> f64 {
+        self.f.max()
+    }
+
+    fn as_constant(&self) -> Option<f64> {
+        self.f.as_constant()
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("reference")
+            .with_reference(&self.id)
+            .with_argument(self.f.as_ref())
+    }
+}
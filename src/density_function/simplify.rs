@@ -0,0 +1,360 @@
+//! Simplifications applied while compiling, they rely on `min()` and `max()`
+//! being sound bounds.
+
+use crate::density_function::clamp::Clamp;
+use crate::density_function::commutative::{Commutative, Operation};
+use crate::density_function::constant::Constant;
+use crate::density_function::deserialize::{DensityFunctionTree, InlineDensityFunctionTree};
+use crate::density_function::range_choice::RangeChoice;
+use crate::density_function::DensityFunction;
+
+/// What the folding needs to know about an operand.
+#[derive(Copy, Clone)]
+pub(crate) struct Operand {
+    pub constant: Option<f64>,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Operand {
+    pub(crate) fn of(f: &dyn DensityFunction) -> Self {
+        Self {
+            constant: f.as_constant(),
+            min: f.min(),
+            max: f.max(),
+        }
+    }
+}
+
+/// What an operation folds into, the backends build it from their own
+/// operands.
+pub(crate) enum Folded {
+    Constant(f64),
+    /// The first operand unchanged, e.g. `when_in_range` of a range choice.
+    First,
+    Second,
+}
+
+pub(crate) fn commutative(
+    f1: Box<dyn DensityFunction>,
+    f2: Box<dyn DensityFunction>,
+    operation: Operation,
+) -> Box<dyn DensityFunction> {
+    match fold_commutative(
+        Operand::of(f1.as_ref()),
+        Operand::of(f2.as_ref()),
+        operation,
+    ) {
+        Some(Folded::Constant(c)) => Constant::new(c),
+        Some(Folded::First) => f1,
+        Some(Folded::Second) => f2,
+        None => Commutative::new(f1, f2, operation),
+    }
+}
+
+pub(crate) fn fold_commutative(a: Operand, b: Operand, operation: Operation) -> Option<Folded> {
+    if let (Some(a), Some(b)) = (a.constant, b.constant) {
+        return Some(Folded::Constant(operation.evaluate(a, b)));
+    }
+
+    if let Some(folded) = identity(a, b, operation, true) {
+        return Some(folded);
+    }
+    if let Some(folded) = identity(b, a, operation, false) {
+        return Some(match folded {
+            Folded::Second => Folded::First,
+            folded => folded,
+        });
+    }
+
+    // strict, as min and max of zeros with different signs may return either
+    match operation {
+        Operation::Min if a.max < b.min => Some(Folded::First),
+        Operation::Min if b.max < a.min => Some(Folded::Second),
+        Operation::Max if a.min > b.max => Some(Folded::First),
+        Operation::Max if b.min > a.max => Some(Folded::Second),
+        _ => None,
+    }
+}
+
+/// Drops the constant `c` if it does not change `f`, folding into
+/// [`Folded::Second`], or replaces both by zero for a multiplication with
+/// zero. Only applies where the result stays the same bit for bit, including
+/// the sign of zeros.
+fn identity(c: Operand, f: Operand, operation: Operation, c_first: bool) -> Option<Folded> {
+    // -0.0 + 0.0 is 0.0, and `Commutative` turns -0.0 * 1.0 into 0.0, as it
+    // skips the second operand if the first one is zero
+    let nonzero = f.min > 0.0 || f.max < 0.0;
+    match (operation, c.constant) {
+        (Operation::Add, Some(0.0)) if nonzero => Some(Folded::Second),
+        (Operation::Multiply, Some(1.0)) if c_first || nonzero => Some(Folded::Second),
+        (Operation::Multiply, Some(0.0)) if c_first => Some(Folded::Constant(0.0)),
+        // infinite values times zero are NaN, not zero. The product has the
+        // sign of `c` for positive `f`, but zero `f` decide it as 0.0
+        (Operation::Multiply, Some(c)) if c == 0.0 && f.max.is_finite() && f.min > 0.0 => {
+            Some(Folded::Constant(c))
+        }
+        (Operation::Multiply, Some(c))
+            if c == 0.0 && f.max.is_finite() && f.min >= 0.0 && c.is_sign_positive() =>
+        {
+            Some(Folded::Constant(c))
+        }
+        (Operation::Multiply, Some(c)) if c == 0.0 && f.min.is_finite() && f.max < 0.0 => {
+            Some(Folded::Constant(-c))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn clamp(f: Box<dyn DensityFunction>, min: f64, max: f64) -> Box<dyn DensityFunction> {
+    match fold_clamp(Operand::of(f.as_ref()), min, max) {
+        Some(Folded::Constant(c)) => Constant::new(c),
+        Some(_) => f,
+        None => Clamp::new(f, min, max),
+    }
+}
+
+/// [`Folded::First`] if the clamp never changes its input.
+pub(crate) fn fold_clamp(input: Operand, min: f64, max: f64) -> Option<Folded> {
+    if let Some(c) = input.constant {
+        return Some(Folded::Constant(c.clamp(min, max)));
+    }
+
+    // strict, as values equal to the bounds are kept with the sign of zeros
+    match (input.min, input.max) {
+        (_, f_max) if f_max < min => Some(Folded::Constant(min)),
+        (f_min, _) if f_min > max => Some(Folded::Constant(max)),
+        (f_min, f_max) if f_min >= min && f_max <= max => Some(Folded::First),
+        _ => None,
+    }
+}
+
+/// Merges clamps of clamps with overlapping ranges into a single clamp of
+/// the innermost input, `clamp(clamp(x, a, b), c, d)` is
+/// `clamp(x, max(a, c), min(b, d))`.
+pub(crate) fn nested_clamps(
+    mut input: &DensityFunctionTree,
+    mut min: f64,
+    mut max: f64,
+) -> (&DensityFunctionTree, f64, f64) {
+    while let DensityFunctionTree::Inline(InlineDensityFunctionTree::Clamp {
+        input: inner,
+        min: inner_min,
+        max: inner_max,
+    }) = input
+    {
+        // disjoint ranges result in a constant, which `clamp` folds
+        if *inner_max < min || *inner_min > max {
+            break;
+        }
+
+        min = min.max(*inner_min);
+        max = max.min(*inner_max);
+        input = inner;
+    }
+
+    (input, min, max)
+}
+
+pub(crate) fn range_choice(
+    input: Box<dyn DensityFunction>,
+    min_inclusive: f64,
+    max_exclusive: f64,
+    when_in_range: Box<dyn DensityFunction>,
+    when_out_of_range: Box<dyn DensityFunction>,
+) -> Box<dyn DensityFunction> {
+    match fold_range_choice(Operand::of(input.as_ref()), min_inclusive, max_exclusive) {
+        Some(Folded::First) => when_in_range,
+        Some(_) => when_out_of_range,
+        None => RangeChoice::new(
+            input,
+            min_inclusive,
+            max_exclusive,
+            when_in_range,
+            when_out_of_range,
+        ),
+    }
+}
+
+/// [`Folded::First`] if `when_in_range` is always chosen,
+/// [`Folded::Second`] if `when_out_of_range` is.
+pub(crate) fn fold_range_choice(
+    input: Operand,
+    min_inclusive: f64,
+    max_exclusive: f64,
+) -> Option<Folded> {
+    if input.min >= min_inclusive && input.max < max_exclusive {
+        return Some(Folded::First);
+    }
+    if input.max < min_inclusive || input.min >= max_exclusive {
+        return Some(Folded::Second);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use valence_core::block_pos::BlockPos;
+
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::noise::deserialize::NoiseSettings;
+    use crate::random::random_state::RandomState;
+    use crate::random::Kind;
+    use crate::registry::memory::MemoryRegistry;
+
+    fn random_state(simplify: bool) -> RandomState {
+        let noise_settings = NoiseSettings {
+            min_y: -64,
+            height: 384,
+            xz_size: 1,
+            y_size: 2,
+        };
+        RandomState::from_parts(
+            noise_settings,
+            &Kind::Xoroshiro,
+            Arc::new(MemoryRegistry::new()),
+            0,
+        )
+        .with_simplification(simplify)
+    }
+
+    /// `y` in -64..320 mapped to -1..1
+    const GRADIENT: &str = r#"{"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": -1, "to_value": 1}"#;
+
+    fn check(json: &str, constant: Option<f64>) {
+        let json = json.replace("GRADIENT", GRADIENT);
+        let tree = serde_json::from_str::<DensityFunctionTree>(&json)
+            .expect("should be a valid density function");
+        let simplified = tree.compile(&random_state(true)).expect("should compile");
+        let reference = tree.compile(&random_state(false)).expect("should compile");
+
+        assert_eq!(constant, simplified.as_constant(), "{json}");
+        for y in (-80..340).step_by(7) {
+            let pos = BlockPos::new(3, y, -5);
+            assert_eq!(
+                reference.compute(pos).to_bits(),
+                simplified.compute(pos).to_bits(),
+                "{json} at {y}"
+            );
+        }
+    }
+
+    #[test]
+    fn simplify_test() {
+        check(
+            r#"{"type": "minecraft:add", "argument1": 1.5, "argument2": 2}"#,
+            Some(3.5),
+        );
+        check(
+            r#"{"type": "minecraft:mul", "argument1": 0, "argument2": GRADIENT}"#,
+            Some(0.0),
+        );
+        // -0.0 below y = 128
+        check(
+            r#"{"type": "minecraft:mul", "argument1": GRADIENT, "argument2": 0}"#,
+            None,
+        );
+        check(
+            r#"{"type": "minecraft:mul", "argument1": {"type": "minecraft:abs", "argument": GRADIENT}, "argument2": 0}"#,
+            Some(0.0),
+        );
+        // -1 * 0 is -0.0, giving 0.0 for 0 and -0.0 above
+        check(
+            r#"{"type": "minecraft:mul", "argument1": {"type": "minecraft:abs", "argument": GRADIENT}, "argument2": {"type": "minecraft:mul", "argument1": -1, "argument2": 0}}"#,
+            None,
+        );
+        check(
+            r#"{"type": "minecraft:mul", "argument1": 1, "argument2": GRADIENT}"#,
+            None,
+        );
+        check(
+            r#"{"type": "minecraft:add", "argument1": GRADIENT, "argument2": 0}"#,
+            None,
+        );
+        check(
+            r#"{"type": "minecraft:max", "argument1": GRADIENT, "argument2": 2}"#,
+            Some(2.0),
+        );
+        check(
+            r#"{"type": "minecraft:min", "argument1": GRADIENT, "argument2": -1.5}"#,
+            Some(-1.5),
+        );
+        check(
+            r#"{"type": "minecraft:min", "argument1": GRADIENT, "argument2": 0.5}"#,
+            None,
+        );
+        check(
+            r#"{"type": "minecraft:clamp", "min": -0.5, "max": 2, "input": {"type": "minecraft:clamp", "min": -2, "max": 0.25, "input": GRADIENT}}"#,
+            None,
+        );
+        check(
+            r#"{"type": "minecraft:clamp", "min": 1, "max": 2, "input": {"type": "minecraft:clamp", "min": -2, "max": 0.25, "input": GRADIENT}}"#,
+            Some(1.0),
+        );
+        check(
+            r#"{"type": "minecraft:range_choice", "input": GRADIENT, "min_inclusive": -1, "max_exclusive": 1.5, "when_in_range": 4, "when_out_of_range": GRADIENT}"#,
+            Some(4.0),
+        );
+        check(
+            r#"{"type": "minecraft:range_choice", "input": GRADIENT, "min_inclusive": 0, "max_exclusive": 1.5, "when_in_range": 4, "when_out_of_range": 5}"#,
+            None,
+        );
+    }
+
+    /// `y` in -64..320 mapped to `from..to`
+    fn gradient(from: f64, to: f64) -> String {
+        format!(
+            r#"{{"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": {from:?}, "to_value": {to:?}}}"#
+        )
+    }
+
+    #[test]
+    fn simplify_bounds_test() {
+        for (from, to, zero) in [(0.5, 0.6, -0.0), (-0.6, -0.5, 0.0)] {
+            let f = gradient(from, to);
+            check(
+                &format!(r#"{{"type": "minecraft:mul", "argument1": {f}, "argument2": -0.0}}"#),
+                Some(zero),
+            );
+        }
+
+        let square = format!(
+            r#"{{"type": "minecraft:square", "argument": {}}}"#,
+            gradient(0.5, 0.6)
+        );
+        // squares of values in 0..1 are smaller than the values
+        check(
+            &format!(r#"{{"type": "minecraft:min", "argument1": {square}, "argument2": 0.3}}"#),
+            None,
+        );
+        check(
+            &format!(r#"{{"type": "minecraft:clamp", "min": 0.4, "max": 1, "input": {square}}}"#),
+            Some(0.4),
+        );
+        check(
+            &format!(
+                r#"{{"type": "minecraft:range_choice", "input": {square}, "min_inclusive": 0.3, "max_exclusive": 1, "when_in_range": 4, "when_out_of_range": 5}}"#
+            ),
+            None,
+        );
+        check(
+            &format!(
+                r#"{{"type": "minecraft:min", "argument1": {{"type": "minecraft:abs", "argument": {}}}, "argument2": 0.4}}"#,
+                gradient(-0.6, -0.5)
+            ),
+            Some(0.4),
+        );
+        // mixed signs, the product is in -6..-1
+        check(
+            &format!(
+                r#"{{"type": "minecraft:max", "argument1": {{"type": "minecraft:mul", "argument1": {}, "argument2": {}}}, "argument2": -1.5}}"#,
+                gradient(1.0, 2.0),
+                gradient(-1.0, -3.0)
+            ),
+            None,
+        );
+    }
+}
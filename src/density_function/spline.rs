@@ -1,5 +1,6 @@
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::describe::Description;
 use crate::density_function::{ContextProvider, DensityFunction};
 use crate::spline::{Built, CubicSpline};
 
@@ -19,4 +20,11 @@ impl DensityFunction for CubicSpline<Built> {
     fn max(&self) -> f64 {
         self.max() as f64
     }
+
+    fn describe(&self) -> Description<'_> {
+        Description {
+            arguments: self.arguments(),
+            ..Description::new("spline")
+        }
+    }
 }
@@ -0,0 +1,793 @@
+//! A backend lowering density functions into a flat list of instructions
+//! over register slots. Slices are filled one instruction at a time, so the
+//! arithmetic between noises runs in plain loops instead of a virtual call
+//! per node and sample. Operations are folded like the tree backend's
+//! simplifications do, if enabled.
+//!
+//! Anything else, e.g. noises, splines and caches, is compiled with the tree
+//! backend and called as a leaf. That includes everything below a leaf: the
+//! arguments of `interpolated`, `flat_cache` and the other caches, and the
+//! shifts of noises are never lowered into the tape, even if they are
+//! arithmetic.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::iter::zip;
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+use valence_core::ident::Ident;
+
+use crate::density_function::clamp::Clamp;
+use crate::density_function::commutative::{Commutative, Operation};
+use crate::density_function::compile::CompileContext;
+use crate::density_function::constant::Constant;
+use crate::density_function::custom;
+use crate::density_function::describe::Description;
+use crate::density_function::deserialize::{DensityFunctionTree, InlineDensityFunctionTree};
+use crate::density_function::range_choice::RangeChoice;
+use crate::density_function::reference::Reference;
+use crate::density_function::simplify::{self, Folded, Operand};
+use crate::density_function::transformer::Mapper;
+use crate::density_function::{ContextProvider, DensityFunction, SubsetContextProvider};
+
+enum Instruction {
+    Constant(f64),
+    Leaf(Arc<dyn DensityFunction>),
+    Map(Mapper, usize),
+    Clamp {
+        input: usize,
+        min: f64,
+        max: f64,
+    },
+    Commutative {
+        operation: Operation,
+        a: usize,
+        b: usize,
+        b_min: f64,
+        b_max: f64,
+    },
+    RangeChoice {
+        input: usize,
+        min_inclusive: f64,
+        max_exclusive: f64,
+        when_in_range: usize,
+        when_out_of_range: usize,
+    },
+}
+
+impl Instruction {
+    fn operands(&mut self) -> Vec<&mut usize> {
+        match self {
+            Instruction::Constant(_) | Instruction::Leaf(_) => vec![],
+            Instruction::Map(_, input) | Instruction::Clamp { input, .. } => vec![input],
+            Instruction::Commutative { a, b, .. } => vec![a, b],
+            Instruction::RangeChoice {
+                input,
+                when_in_range,
+                when_out_of_range,
+                ..
+            } => vec![input, when_in_range, when_out_of_range],
+        }
+    }
+
+    /// The value at `idx`, the values of the operands are `value(register,
+    /// idx)`.
+    fn evaluate(&self, idx: usize, value: impl Fn(usize, usize) -> f64) -> f64 {
+        match *self {
+            Instruction::Constant(c) => c,
+            Instruction::Leaf(_) => unreachable!("leaves are evaluated by their caller"),
+            Instruction::Map(mapper, input) => mapper.apply(value(input, idx)),
+            Instruction::Clamp { input, min, max } => value(input, idx).clamp(min, max),
+            Instruction::Commutative {
+                operation,
+                a,
+                b,
+                b_min,
+                b_max,
+            } => {
+                let a = value(a, idx);
+                operation
+                    .decided(a, b_min, b_max)
+                    .unwrap_or_else(|| operation.apply(a, value(b, idx)))
+            }
+            Instruction::RangeChoice {
+                input,
+                min_inclusive,
+                max_exclusive,
+                when_in_range,
+                when_out_of_range,
+            } => {
+                let choice = value(input, idx);
+                match choice >= min_inclusive && choice < max_exclusive {
+                    true => value(when_in_range, idx),
+                    false => value(when_out_of_range, idx),
+                }
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Slots reused by every tape evaluated on this thread. A tape called by
+    /// a leaf of another tape finds them taken and allocates its own.
+    static COMPUTE_SLOTS: Cell<Vec<f64>> = const { Cell::new(vec![]) };
+    static FILL_SLOTS: Cell<Vec<Vec<f64>>> = const { Cell::new(vec![]) };
+}
+
+/// The instructions in order with the slot each one writes to, operands are
+/// the registers, i.e. indices, of the instructions writing them.
+pub struct Tape {
+    instructions: Vec<(Instruction, usize)>,
+    /// The registers reading each register.
+    consumers: Vec<Vec<usize>>,
+    slots: usize,
+    output: usize,
+    min: f64,
+    max: f64,
+    /// The instructions as tree nodes, see [`describe_instructions`].
+    described: Arc<dyn DensityFunction>,
+}
+
+impl DensityFunction for Tape {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        // every slot is written before it's read
+        let mut slots = COMPUTE_SLOTS.take();
+        if slots.len() < self.slots {
+            slots.resize(self.slots, 0.0);
+        }
+
+        for (instruction, slot) in &self.instructions {
+            let value = match instruction {
+                Instruction::Leaf(f) => f.compute(pos),
+                instruction => instruction.evaluate(0, |register, _| slots[self.slot(register)]),
+            };
+            slots[*slot] = value;
+        }
+
+        let value = slots[self.slot(self.output)];
+        COMPUTE_SLOTS.set(slots);
+        value
+    }
+
+    /// Fills every instruction over the indices its value is needed at only,
+    /// like the tree backend, see [`active`](Self::active).
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        let len = slice.len();
+        let mut slots = FILL_SLOTS.take();
+        if slots.len() < self.slots {
+            slots.resize(self.slots, vec![]);
+        }
+
+        let mut active = vec![None; self.instructions.len()];
+        active[self.output] = Some((0..len).collect());
+        for (register, (instruction, slot)) in self.instructions.iter().enumerate() {
+            self.active(register, register, &mut active, &slots, len);
+            let indices = active[register].take().unwrap_or_default();
+
+            // slots are never read by the instruction writing them
+            let mut values = std::mem::take(&mut slots[*slot]);
+            values.resize(len, 0.0);
+
+            match instruction {
+                Instruction::Constant(c) => values.fill(*c),
+                Instruction::Leaf(f) if indices.len() == len => {
+                    f.fill(&mut values, context_provider)
+                }
+                Instruction::Leaf(f) if !indices.is_empty() => {
+                    let mut subset = vec![0.0; indices.len()];
+                    f.fill(
+                        &mut subset,
+                        &SubsetContextProvider::new(context_provider, &indices),
+                    );
+                    zip(&indices, subset).for_each(|(&i, v)| values[i] = v);
+                }
+                Instruction::Leaf(_) => {}
+                instruction => indices.iter().for_each(|&i| {
+                    values[i] = instruction.evaluate(i, |register, i| slots[self.slot(register)][i])
+                }),
+            }
+
+            slots[*slot] = values;
+        }
+
+        slice.copy_from_slice(&slots[self.slot(self.output)][..len]);
+        FILL_SLOTS.set(slots);
+    }
+
+    fn min(&self) -> f64 {
+        self.min
+    }
+
+    fn max(&self) -> f64 {
+        self.max
+    }
+
+    fn as_constant(&self) -> Option<f64> {
+        match self.instructions.as_slice() {
+            [(Instruction::Constant(c), _)] => Some(*c),
+            [(Instruction::Leaf(f), _)] => f.as_constant(),
+            _ => None,
+        }
+    }
+
+    /// The tape with the instruction writing the result as its argument.
+    fn describe(&self) -> Description<'_> {
+        Description::new("tape").with_argument(self.described.as_ref())
+    }
+}
+
+impl Tape {
+    fn slot(&self, register: usize) -> usize {
+        self.instructions[register].1
+    }
+
+    /// Computes the indices `register` is needed at into `active`, the union
+    /// of the indices each consumer reads it at. The second operand of
+    /// `mul`, `min` and `max` is only read where the first one doesn't decide
+    /// the result and the branches of `range_choice` where they are chosen,
+    /// if the deciding register was evaluated before `now`. Otherwise it's
+    /// read wherever its consumer is needed.
+    fn active(
+        &self,
+        register: usize,
+        now: usize,
+        active: &mut [Option<Vec<usize>>],
+        slots: &[Vec<f64>],
+        len: usize,
+    ) {
+        if active[register].is_some() {
+            return;
+        }
+
+        let mut needed = vec![false; len];
+        for &consumer in &self.consumers[register] {
+            self.active(consumer, now, active, slots, len);
+            let indices = active[consumer].as_deref().unwrap_or_default();
+
+            let read = |i: usize| match self.instructions[consumer].0 {
+                Instruction::Commutative {
+                    operation,
+                    a,
+                    b,
+                    b_min,
+                    b_max,
+                } if register == b && a != b && a < now => operation
+                    .decided(slots[self.slot(a)][i], b_min, b_max)
+                    .is_none(),
+                Instruction::RangeChoice {
+                    input,
+                    min_inclusive,
+                    max_exclusive,
+                    when_in_range,
+                    when_out_of_range,
+                } if register != input && when_in_range != when_out_of_range && input < now => {
+                    let choice = slots[self.slot(input)][i];
+                    let in_range = choice >= min_inclusive && choice < max_exclusive;
+                    in_range == (register == when_in_range)
+                }
+                _ => true,
+            };
+            indices
+                .iter()
+                .filter(|&&i| read(i))
+                .for_each(|&i| needed[i] = true);
+        }
+
+        active[register] = Some((0..len).filter(|&i| needed[i]).collect());
+    }
+}
+
+/// A node per instruction, like the tree backend would have compiled it, with
+/// the instructions it reads as arguments. Instructions lowered from a
+/// reference are wrapped in a `reference` node with its id.
+fn describe_instructions(
+    instructions: &[Instruction],
+    references: &[Vec<Ident<String>>],
+) -> Arc<dyn DensityFunction> {
+    let mut nodes: Vec<Arc<dyn DensityFunction>> = Vec::with_capacity(instructions.len());
+    for (instruction, ids) in zip(instructions, references) {
+        let operand =
+            |register: usize| -> Box<dyn DensityFunction> { Box::new(nodes[register].clone()) };
+        let mut node: Arc<dyn DensityFunction> = match *instruction {
+            Instruction::Constant(c) => Arc::from(Constant::new(c)),
+            Instruction::Leaf(ref f) => f.clone(),
+            Instruction::Map(mapper, input) => Arc::from(mapper.map(operand(input))),
+            Instruction::Clamp { input, min, max } => {
+                Arc::from(Clamp::new(operand(input), min, max))
+            }
+            Instruction::Commutative {
+                operation, a, b, ..
+            } => Arc::from(Commutative::new(operand(a), operand(b), operation)),
+            Instruction::RangeChoice {
+                input,
+                min_inclusive,
+                max_exclusive,
+                when_in_range,
+                when_out_of_range,
+            } => Arc::from(RangeChoice::new(
+                operand(input),
+                min_inclusive,
+                max_exclusive,
+                operand(when_in_range),
+                operand(when_out_of_range),
+            )),
+        };
+        for id in ids {
+            node = Arc::from(Reference::new(id.clone(), node));
+        }
+        nodes.push(node);
+    }
+
+    nodes.pop().expect("a tape has at least one instruction")
+}
+
+pub(crate) fn compile(
+    tree: &DensityFunctionTree,
+    ctx: &mut CompileContext,
+) -> eyre::Result<Box<dyn DensityFunction>> {
+    let mut builder = TapeBuilder::new(ctx);
+    let output = builder.lower(tree)?;
+    Ok(Box::new(builder.finish(output)))
+}
+
+pub(crate) fn compile_inline(
+    tree: &InlineDensityFunctionTree,
+    ctx: &mut CompileContext,
+) -> eyre::Result<Box<dyn DensityFunction>> {
+    let mut builder = TapeBuilder::new(ctx);
+    let output = builder.lower_inline(tree)?;
+    Ok(Box::new(builder.finish(output)))
+}
+
+/// Lowers trees into instructions in single assignment form, each
+/// instruction writes its own register, which are mapped to slots at last.
+struct TapeBuilder<'c, 'a> {
+    ctx: &'c mut CompileContext<'a>,
+    instructions: Vec<Instruction>,
+    bounds: Vec<(f64, f64)>,
+    /// References lowered already, evaluated once per sample.
+    references: HashMap<Ident<String>, usize>,
+}
+
+impl<'c, 'a> TapeBuilder<'c, 'a> {
+    fn new(ctx: &'c mut CompileContext<'a>) -> Self {
+        Self {
+            ctx,
+            instructions: vec![],
+            bounds: vec![],
+            references: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, instruction: Instruction, bounds: (f64, f64)) -> usize {
+        self.instructions.push(instruction);
+        self.bounds.push(bounds);
+        self.instructions.len() - 1
+    }
+
+    fn operand(&self, register: usize) -> Operand {
+        let (min, max) = self.bounds[register];
+        let constant = match &self.instructions[register] {
+            Instruction::Constant(c) => Some(*c),
+            Instruction::Leaf(f) => f.as_constant(),
+            _ => None,
+        };
+        Operand { constant, min, max }
+    }
+
+    /// The register `folded` refers to, `first` and `second` are the
+    /// operands of the folded operation.
+    fn folded(&mut self, folded: Folded, first: usize, second: usize) -> usize {
+        match folded {
+            Folded::Constant(c) => self.push(Instruction::Constant(c), (c, c)),
+            Folded::First => first,
+            Folded::Second => second,
+        }
+    }
+
+    fn lower(&mut self, tree: &DensityFunctionTree) -> eyre::Result<usize> {
+        self.ctx.enter()?;
+        let result = match tree {
+            DensityFunctionTree::Constant(c) => Ok(self.push(Instruction::Constant(*c), (*c, *c))),
+            DensityFunctionTree::Reference(id) => {
+                let id = Ident::new(id.as_str())?.to_string_ident();
+                self.reference(id)
+            }
+            DensityFunctionTree::Inline(f) => self.lower_inline(f),
+            DensityFunctionTree::Custom(f) => custom::compile(f, self.ctx).map(|f| {
+                let bounds = (f.min(), f.max());
+                self.push(Instruction::Leaf(Arc::from(f)), bounds)
+            }),
+        };
+        self.ctx.leave();
+        result
+    }
+
+    fn reference(&mut self, id: Ident<String>) -> eyre::Result<usize> {
+        if let Some(register) = self.references.get(&id) {
+            return Ok(*register);
+        }
+
+        self.ctx.push_reference(id.as_str())?;
+        let result = self
+            .ctx
+            .random_state
+            .registry
+            .density_function(&id.as_str_ident())
+            .and_then(|f| self.lower(&f));
+        self.ctx.pop_reference();
+
+        let register = result?;
+        self.references.insert(id, register);
+        Ok(register)
+    }
+
+    fn lower_inline(&mut self, tree: &InlineDensityFunctionTree) -> eyre::Result<usize> {
+        let (mapper, argument) = match tree {
+            InlineDensityFunctionTree::Constant { argument } => {
+                return Ok(self.push(Instruction::Constant(*argument), (*argument, *argument)))
+            }
+            InlineDensityFunctionTree::CacheAllInCell { argument }
+            | InlineDensityFunctionTree::BlendDensity { argument } => return self.lower(argument),
+
+            InlineDensityFunctionTree::Add {
+                argument1,
+                argument2,
+            } => return self.commutative(argument1, argument2, Operation::Add),
+            InlineDensityFunctionTree::Mul {
+                argument1,
+                argument2,
+            } => return self.commutative(argument1, argument2, Operation::Multiply),
+            InlineDensityFunctionTree::Min {
+                argument1,
+                argument2,
+            } => return self.commutative(argument1, argument2, Operation::Min),
+            InlineDensityFunctionTree::Max {
+                argument1,
+                argument2,
+            } => return self.commutative(argument1, argument2, Operation::Max),
+
+            InlineDensityFunctionTree::Clamp { input, min, max } => {
+                let (input, min, max) = match self.ctx.random_state.simplify {
+                    true => simplify::nested_clamps(input, *min, *max),
+                    false => (&**input, *min, *max),
+                };
+                let input = self.lower(input)?;
+                if self.ctx.random_state.simplify {
+                    if let Some(folded) = simplify::fold_clamp(self.operand(input), min, max) {
+                        return Ok(self.folded(folded, input, input));
+                    }
+                }
+
+                return Ok(self.push(Instruction::Clamp { input, min, max }, (min, max)));
+            }
+            InlineDensityFunctionTree::RangeChoice {
+                input,
+                min_inclusive,
+                max_exclusive,
+                when_in_range,
+                when_out_of_range,
+            } => {
+                let input = self.lower(input)?;
+                let when_in_range = self.lower(when_in_range)?;
+                let when_out_of_range = self.lower(when_out_of_range)?;
+                if self.ctx.random_state.simplify {
+                    if let Some(folded) = simplify::fold_range_choice(
+                        self.operand(input),
+                        *min_inclusive,
+                        *max_exclusive,
+                    ) {
+                        return Ok(self.folded(folded, when_in_range, when_out_of_range));
+                    }
+                }
+
+                let (a, b) = (self.bounds[when_in_range], self.bounds[when_out_of_range]);
+                return Ok(self.push(
+                    Instruction::RangeChoice {
+                        input,
+                        min_inclusive: *min_inclusive,
+                        max_exclusive: *max_exclusive,
+                        when_in_range,
+                        when_out_of_range,
+                    },
+                    (f64::min(a.0, b.0), f64::max(a.1, b.1)),
+                ));
+            }
+
+            InlineDensityFunctionTree::Abs { argument } => (Mapper::Abs, argument),
+            InlineDensityFunctionTree::Square { argument } => (Mapper::Square, argument),
+            InlineDensityFunctionTree::Cube { argument } => (Mapper::Cube, argument),
+            InlineDensityFunctionTree::HalfNegative { argument } => {
+                (Mapper::HalfNegative, argument)
+            }
+            InlineDensityFunctionTree::QuarterNegative { argument } => {
+                (Mapper::QuarterNegative, argument)
+            }
+            InlineDensityFunctionTree::Squeeze { argument } => (Mapper::Squeeze, argument),
+            InlineDensityFunctionTree::Invert { argument } => (Mapper::Invert, argument),
+
+            _ => {
+                let f = tree.compile_with(self.ctx)?;
+                let bounds = (f.min(), f.max());
+                return Ok(self.push(Instruction::Leaf(Arc::from(f)), bounds));
+            }
+        };
+
+        let input = self.lower(argument)?;
+        let (min, max) = self.bounds[input];
+        Ok(self.push(Instruction::Map(mapper, input), mapper.bounds(min, max)))
+    }
+
+    fn commutative(
+        &mut self,
+        argument1: &DensityFunctionTree,
+        argument2: &DensityFunctionTree,
+        operation: Operation,
+    ) -> eyre::Result<usize> {
+        let a = self.lower(argument1)?;
+        let b = self.lower(argument2)?;
+        if self.ctx.random_state.simplify {
+            let (a_operand, b_operand) = (self.operand(a), self.operand(b));
+            if let Some(folded) = simplify::fold_commutative(a_operand, b_operand, operation) {
+                return Ok(self.folded(folded, a, b));
+            }
+        }
+
+        let (b_min, b_max) = self.bounds[b];
+        Ok(self.push(
+            Instruction::Commutative {
+                operation,
+                a,
+                b,
+                b_min,
+                b_max,
+            },
+            operation.bounds(self.bounds[a], self.bounds[b]),
+        ))
+    }
+
+    /// Drops instructions `output` does not depend on, like the operands of
+    /// folded operations, and maps registers to slots, a slot is reused once
+    /// the last instruction reading its register ran.
+    fn finish(self, output: usize) -> Tape {
+        let (min, max) = self.bounds[output];
+
+        let mut instructions = self.instructions;
+        let mut live = vec![false; instructions.len()];
+        live[output] = true;
+        for i in (0..instructions.len()).rev() {
+            if live[i] {
+                for register in instructions[i].operands() {
+                    live[*register] = true;
+                }
+            }
+        }
+
+        // registers are only read by later instructions, `output` is the last
+        // one kept
+        let mut renamed = vec![0; instructions.len()];
+        let mut kept = vec![];
+        for (i, (mut instruction, &live)) in zip(instructions, &live).enumerate() {
+            if live {
+                for register in instruction.operands() {
+                    *register = renamed[*register];
+                }
+                renamed[i] = kept.len();
+                kept.push(instruction);
+            }
+        }
+        let (output, mut instructions) = (renamed[output], kept);
+
+        let mut references = vec![vec![]; instructions.len()];
+        for (id, register) in self.references {
+            if live[register] {
+                references[renamed[register]].push(id);
+            }
+        }
+        references.iter_mut().for_each(|ids| ids.sort());
+        let described = describe_instructions(&instructions, &references);
+
+        let mut last_read = (0..instructions.len()).collect::<Vec<_>>();
+        let mut consumers = vec![vec![]; instructions.len()];
+        for (i, instruction) in instructions.iter_mut().enumerate() {
+            for register in instruction.operands() {
+                last_read[*register] = i;
+                if !consumers[*register].contains(&i) {
+                    consumers[*register].push(i);
+                }
+            }
+        }
+
+        let mut slot_of = vec![0; instructions.len()];
+        let mut free = vec![];
+        let mut slots = 0;
+        let instructions = zip(0.., instructions)
+            .map(|(i, mut instruction)| {
+                let mut read = instruction
+                    .operands()
+                    .into_iter()
+                    .map(|register| *register)
+                    .collect::<Vec<_>>();
+
+                slot_of[i] = free.pop().unwrap_or_else(|| {
+                    slots += 1;
+                    slots - 1
+                });
+
+                read.push(i);
+                read.sort_unstable();
+                read.dedup();
+                for register in read {
+                    if last_read[register] == i && register != output {
+                        free.push(slot_of[register]);
+                    }
+                }
+
+                (instruction, slot_of[i])
+            })
+            .collect();
+
+        Tape {
+            instructions,
+            consumers,
+            slots,
+            output,
+            min,
+            max,
+            described,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use valence_core::block_pos::BlockPos;
+    use valence_core::ident;
+    use valence_core::ident::Ident;
+
+    use crate::density_function::compile::Backend;
+    use crate::density_function::custom;
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::density_function::test::{Column, Counting};
+    use crate::density_function::y_clamped_gradient::YClampedGradient;
+    use crate::density_function::ContextProvider;
+    use crate::noise::deserialize::{NoiseParameters, NoiseSettings};
+    use crate::random::random_state::RandomState;
+    use crate::random::Kind;
+    use crate::registry::memory::MemoryRegistry;
+
+    const FUNCTIONS: &[(&str, &str)] = &[
+        (
+            "test:n",
+            r#"{"type": "minecraft:noise", "noise": "test:noise", "xz_scale": 0.5, "y_scale": 2}"#,
+        ),
+        (
+            "test:a",
+            r#"{"type": "minecraft:add", "argument1": {"type": "minecraft:mul", "argument1": "test:n", "argument2": 0}, "argument2": {"type": "minecraft:clamp", "min": 0, "max": 0.3, "input": {"type": "minecraft:abs", "argument": "test:n"}}}"#,
+        ),
+        (
+            "test:range",
+            r#"{"type": "minecraft:range_choice", "input": {"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": -1, "to_value": 1}, "min_inclusive": -0.25, "max_exclusive": 0.5, "when_in_range": {"type": "minecraft:squeeze", "argument": "test:n"}, "when_out_of_range": {"type": "minecraft:half_negative", "argument": {"type": "minecraft:min", "argument1": "test:n", "argument2": 0.1}}}"#,
+        ),
+        (
+            "test:folded",
+            r#"{"type": "minecraft:range_choice", "input": {"type": "minecraft:clamp", "min": 0.2, "max": 0.4, "input": "test:n"}, "min_inclusive": 0, "max_exclusive": 1, "when_in_range": {"type": "minecraft:mul", "argument1": 0, "argument2": "test:n"}, "when_out_of_range": "test:n"}"#,
+        ),
+        (
+            "test:final",
+            r#"{"type": "minecraft:max", "argument1": {"type": "minecraft:mul", "argument1": "test:a", "argument2": "test:range"}, "argument2": {"type": "minecraft:invert", "argument": {"type": "minecraft:add", "argument1": {"type": "minecraft:cube", "argument": {"type": "minecraft:flat_cache", "argument": "test:n"}}, "argument2": 3}}}"#,
+        ),
+    ];
+
+    fn random_state(backend: Backend, simplify: bool) -> RandomState {
+        let registry = MemoryRegistry::new().with_noise(
+            &ident!("test:noise"),
+            NoiseParameters::new(-5, vec![1.0, 0.5]),
+        );
+        for (id, json) in FUNCTIONS {
+            registry.insert_density_function(
+                &Ident::new(*id).unwrap().as_str_ident(),
+                serde_json::from_str::<DensityFunctionTree>(json)
+                    .expect("should be a valid density function"),
+            );
+        }
+
+        let noise_settings = NoiseSettings {
+            min_y: -64,
+            height: 384,
+            xz_size: 1,
+            y_size: 2,
+        };
+        RandomState::from_parts(noise_settings, &Kind::Xoroshiro, Arc::new(registry), 7)
+            .with_backend(backend)
+            .with_simplification(simplify)
+    }
+
+    #[test]
+    fn tape_test() {
+        for simplify in [true, false] {
+            let (tree_state, tape_state) = (
+                random_state(Backend::Tree, simplify),
+                random_state(Backend::Tape, simplify),
+            );
+
+            for (id, _) in FUNCTIONS {
+                let function = DensityFunctionTree::Reference(id.to_string());
+                let tree = function.compile(&tree_state).expect("should compile");
+                let tape = function.compile(&tape_state).expect("should compile");
+                if *id == "test:folded" {
+                    let folded = simplify.then_some(0.0);
+                    assert_eq!(folded, tree.as_constant());
+                    assert_eq!(folded, tape.as_constant());
+                }
+
+                let (mut tree_values, mut tape_values) = ([0.0; 384], [0.0; 384]);
+                tree.fill(&mut tree_values, &Column);
+                tape.fill(&mut tape_values, &Column);
+                for (i, (a, b)) in tree_values.into_iter().zip(tape_values).enumerate() {
+                    let pos = Column.for_index(i);
+                    assert_eq!(a.to_bits(), b.to_bits(), "{id} filled at {pos:?}");
+                    assert_eq!(
+                        tree.compute(pos).to_bits(),
+                        tape.compute(pos).to_bits(),
+                        "{id} at {pos:?}"
+                    );
+                }
+
+                for x in (-200..200).step_by(37) {
+                    let pos = BlockPos::new(x, x / 3, -x * 2);
+                    assert_eq!(
+                        tree.compute(pos).to_bits(),
+                        tape.compute(pos).to_bits(),
+                        "{id} at {pos:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tape_fill_subsets_test() {
+        let counted = |id: &str, from_value, to_value| {
+            let f = Counting::new(YClampedGradient::new(-64, 320, from_value, to_value).unwrap());
+            let leaf = f.clone();
+            custom::register(
+                Ident::new(id).unwrap().to_string_ident(),
+                move |_: serde_json::Value, _| Ok(Box::new(leaf.clone())),
+            )
+            .unwrap();
+            f
+        };
+        let (b, in_range, out_of_range) = (
+            counted("test:tape_b", 0.0, 0.5),
+            counted("test:tape_in_range", 2.0, 3.0),
+            counted("test:tape_out_of_range", -3.0, -2.0),
+        );
+
+        // the gradient is below 0, and decides the min, for y < 128 and in
+        // range for 128 <= y < 224
+        let gradient = r#"{"type": "minecraft:y_clamped_gradient", "from_y": -64, "to_y": 320, "from_value": -1, "to_value": 1}"#;
+        let json = format!(
+            r#"{{"type": "minecraft:add", "argument1": {{"type": "minecraft:min", "argument1": {gradient}, "argument2": {{"type": "test:tape_b"}}}}, "argument2": {{"type": "minecraft:range_choice", "input": {gradient}, "min_inclusive": 0, "max_exclusive": 0.5, "when_in_range": {{"type": "test:tape_in_range"}}, "when_out_of_range": {{"type": "test:tape_out_of_range"}}}}}}"#
+        );
+        let tape = serde_json::from_str::<DensityFunctionTree>(&json)
+            .unwrap()
+            .compile(&random_state(Backend::Tape, true))
+            .expect("should compile");
+
+        let mut values = [0.0; 384];
+        tape.fill(&mut values, &Column);
+        for (f, calls) in [(&b, 192), (&in_range, 96), (&out_of_range, 288)] {
+            assert_eq!(1, f.fills());
+            assert_eq!(calls, f.calls());
+        }
+
+        for (i, v) in values.into_iter().enumerate() {
+            let pos = Column.for_index(i);
+            assert_eq!(tape.compute(pos).to_bits(), v.to_bits(), "at {pos:?}");
+        }
+    }
+}
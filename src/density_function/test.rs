@@ -1,4 +1,10 @@
-use crate::density_function::deserialize::DensityFunctionTree;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+
+use crate::density_function::deserialize::{DensityFunctionTree, InlineDensityFunctionTree};
+use crate::density_function::{ContextProvider, DensityFunction};
 
 #[test]
 fn parse_density_function() {
@@ -8,6 +14,67 @@ fn parse_density_function() {
     assert!(result.is_ok())
 }
 
+/// A column of 384 blocks at `x = z = 0`, starting at `y = -64`.
+pub(crate) struct Column;
+
+impl ContextProvider for Column {
+    fn for_index(&self, idx: usize) -> BlockPos {
+        BlockPos::new(0, idx as i32 - 64, 0)
+    }
+
+    fn fill_direct(&self, slice: &mut [f64], filler: &dyn DensityFunction) {
+        for (i, v) in slice.iter_mut().enumerate() {
+            *v = filler.compute(self.for_index(i));
+        }
+    }
+}
+
+pub(crate) struct Counting {
+    f: Box<dyn DensityFunction>,
+    calls: AtomicUsize,
+    fills: AtomicUsize,
+}
+
+impl Counting {
+    pub(crate) fn new(f: Box<dyn DensityFunction>) -> Arc<Self> {
+        Arc::new(Self {
+            f,
+            calls: AtomicUsize::new(0),
+            fills: AtomicUsize::new(0),
+        })
+    }
+
+    /// The number of `compute` calls since the last call.
+    pub(crate) fn calls(&self) -> usize {
+        self.calls.swap(0, Ordering::Relaxed)
+    }
+
+    /// The number of `fill` calls since the last call.
+    pub(crate) fn fills(&self) -> usize {
+        self.fills.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl DensityFunction for Counting {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.f.compute(pos)
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        self.fills.fetch_add(1, Ordering::Relaxed);
+        context_provider.fill_direct(slice, self)
+    }
+
+    fn min(&self) -> f64 {
+        self.f.min()
+    }
+
+    fn max(&self) -> f64 {
+        self.f.max()
+    }
+}
+
 const SAMPLE: &str = r#"
 {
   "type": "minecraft:cache_once",
@@ -93,3 +160,63 @@ const SAMPLE: &str = r#"
   }
 }
 "#;
+
+#[test]
+fn parse_end_and_newer_density_functions() {
+    use DensityFunctionTree::{Constant, Inline, Reference};
+    use InlineDensityFunctionTree as F;
+
+    let d = &mut serde_json::Deserializer::from_str(END_SAMPLE);
+    let tree: DensityFunctionTree =
+        serde_path_to_error::deserialize(d).expect("should be a valid density function");
+
+    let Inline(F::Add {
+        argument1,
+        argument2,
+    }) = &tree
+    else {
+        panic!("should be an add");
+    };
+    let Inline(F::Cache2D { argument }) = argument1.as_ref() else {
+        panic!("argument1 should be a cache_2d");
+    };
+    assert!(matches!(argument.as_ref(), Inline(F::EndIslands {})));
+
+    let Inline(F::FindTopSurface {
+        density,
+        upper_bound,
+        lower_bound,
+        cell_height,
+    }) = argument2.as_ref()
+    else {
+        panic!("argument2 should be a find_top_surface");
+    };
+    assert!(matches!(upper_bound.as_ref(), Constant(v) if *v == 256.0));
+    assert_eq!((0, 4), (*lower_bound, *cell_height));
+    let Inline(F::Invert { argument }) = density.as_ref() else {
+        panic!("density should be an invert");
+    };
+    assert!(matches!(argument.as_ref(), Reference(id) if id == "minecraft:end/base_3d_noise"));
+}
+
+const END_SAMPLE: &str = r#"
+{
+  "type": "minecraft:add",
+  "argument1": {
+    "type": "minecraft:cache_2d",
+    "argument": {
+      "type": "minecraft:end_islands"
+    }
+  },
+  "argument2": {
+    "type": "minecraft:find_top_surface",
+    "density": {
+      "type": "minecraft:invert",
+      "argument": "minecraft:end/base_3d_noise"
+    },
+    "upper_bound": 256,
+    "lower_bound": 0,
+    "cell_height": 4
+  }
+}
+"#;
@@ -2,20 +2,88 @@ use std::sync::Arc;
 
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::describe::Description;
 use crate::density_function::{sort_min_max, ContextProvider, DensityFunction};
 
 pub struct Transformer<T: Fn(f64) -> f64 + Sync> {
+    kind: &'static str,
     f: Box<dyn DensityFunction>,
     transform: Arc<T>,
     min: f64,
     max: f64,
 }
 
-impl<T: Fn(f64) -> f64 + 'static + Sync + Send> Transformer<T> {
-    pub fn new(f: Box<dyn DensityFunction>, transform: Arc<T>) -> Box<dyn DensityFunction> {
-        let (min, max) = sort_min_max(transform(f.min()), transform(f.max()));
+/// The transforms of the functions taking a single argument, like vanilla's
+/// `Mapped` types.
+#[derive(Copy, Clone)]
+pub(crate) enum Mapper {
+    Abs,
+    Square,
+    Cube,
+    HalfNegative,
+    QuarterNegative,
+    Squeeze,
+    Invert,
+}
+
+impl Mapper {
+    pub(crate) fn apply(self, x: f64) -> f64 {
+        match self {
+            Mapper::Abs => x.abs(),
+            Mapper::Square => x.powi(2),
+            Mapper::Cube => x.powi(3),
+            Mapper::HalfNegative => 0.5 * (-x),
+            Mapper::QuarterNegative => 0.25 * (-x),
+            Mapper::Squeeze => {
+                let clamped = x.clamp(-1.0, 1.0);
+                (clamped / 2.0) - (clamped.powi(3) / 24.0)
+            }
+            Mapper::Invert => 1.0 / x,
+        }
+    }
+
+    /// The bounds of the transform of an input within `min..=max`.
+    pub(crate) fn bounds(self, min: f64, max: f64) -> (f64, f64) {
+        match self {
+            Mapper::Abs | Mapper::Square if min >= 0.0 => (self.apply(min), self.apply(max)),
+            Mapper::Abs | Mapper::Square if max <= 0.0 => (self.apply(max), self.apply(min)),
+            Mapper::Abs | Mapper::Square => (0.0, self.apply(min).max(self.apply(max))),
+            // 1/x is unbounded in case the input interval contains zero, which
+            // may be -0.0
+            Mapper::Invert if min <= 0.0 && max >= 0.0 => (f64::NEG_INFINITY, f64::INFINITY),
+            _ => sort_min_max(self.apply(min), self.apply(max)),
+        }
+    }
+
+    /// The vanilla type name.
+    pub(crate) fn kind(self) -> &'static str {
+        match self {
+            Mapper::Abs => "abs",
+            Mapper::Square => "square",
+            Mapper::Cube => "cube",
+            Mapper::HalfNegative => "half_negative",
+            Mapper::QuarterNegative => "quarter_negative",
+            Mapper::Squeeze => "squeeze",
+            Mapper::Invert => "invert",
+        }
+    }
+
+    pub(crate) fn map(self, f: Box<dyn DensityFunction>) -> Box<dyn DensityFunction> {
+        let (min, max) = self.bounds(f.min(), f.max());
+        Transformer::new_with_bounds(self.kind(), f, Arc::new(move |x| self.apply(x)), min, max)
+    }
+}
 
+impl<T: Fn(f64) -> f64 + 'static + Sync + Send> Transformer<T> {
+    pub fn new_with_bounds(
+        kind: &'static str,
+        f: Box<dyn DensityFunction>,
+        transform: Arc<T>,
+        min: f64,
+        max: f64,
+    ) -> Box<dyn DensityFunction> {
         Box::new(Transformer {
+            kind,
             f,
             transform,
             min,
@@ -41,4 +109,8 @@ impl<T: Fn(f64) -> f64 + 'static + Sync + Send> DensityFunction for Transformer<
     fn max(&self) -> f64 {
         self.max
     }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new(self.kind).with_argument(self.f.as_ref())
+    }
 }
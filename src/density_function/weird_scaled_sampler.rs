@@ -0,0 +1,88 @@
+use std::simd::f64x4;
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+use valence_core::ident::Ident;
+
+use crate::density_function::describe::Description;
+use crate::density_function::deserialize::RarityValueMapper;
+use crate::density_function::{ContextProvider, DensityFunction};
+use crate::noise::normal::NormalNoise;
+
+pub struct WeirdScaledSampler {
+    input: Box<dyn DensityFunction>,
+    id: Ident<String>,
+    noise: Arc<NormalNoise>,
+    rarity_value_mapper: RarityValueMapper,
+}
+
+impl WeirdScaledSampler {
+    pub fn new(
+        input: Box<dyn DensityFunction>,
+        id: Ident<String>,
+        noise: Arc<NormalNoise>,
+        rarity_value_mapper: RarityValueMapper,
+    ) -> Box<dyn DensityFunction> {
+        Box::new(Self {
+            input,
+            id,
+            noise,
+            rarity_value_mapper,
+        })
+    }
+}
+
+impl RarityValueMapper {
+    fn rarity(&self, v: f64) -> f64 {
+        match self {
+            RarityValueMapper::Type1 => match v {
+                v if v < -0.5 => 0.75,
+                v if v < 0.0 => 1.0,
+                v if v < 0.5 => 1.5,
+                _ => 2.0,
+            },
+            RarityValueMapper::Type2 => match v {
+                v if v < -0.75 => 0.5,
+                v if v < -0.5 => 0.75,
+                v if v < 0.5 => 1.0,
+                v if v < 0.75 => 2.0,
+                _ => 3.0,
+            },
+        }
+    }
+
+    fn max_rarity(&self) -> f64 {
+        match self {
+            RarityValueMapper::Type1 => 2.0,
+            RarityValueMapper::Type2 => 3.0,
+        }
+    }
+}
+
+impl DensityFunction for WeirdScaledSampler {
+    fn compute(&self, pos: BlockPos) -> f64 {
+        let rarity = self.rarity_value_mapper.rarity(self.input.compute(pos));
+        let input = f64x4::from_array([pos.x as f64, pos.y as f64, pos.z as f64, 0.0])
+            / f64x4::splat(rarity);
+
+        rarity * self.noise.get_value(input).abs()
+    }
+
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        context_provider.fill_direct(slice, self)
+    }
+
+    fn min(&self) -> f64 {
+        0.0
+    }
+
+    fn max(&self) -> f64 {
+        self.rarity_value_mapper.max_rarity() * self.noise.max()
+    }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("weird_scaled_sampler")
+            .with_noise(&self.id)
+            .with_argument(self.input.as_ref())
+    }
+}
@@ -1,5 +1,6 @@
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::describe::Description;
 use crate::density_function::{ContextProvider, DensityFunction};
 
 pub struct YClampedGradient {
@@ -28,8 +29,8 @@ impl DensityFunction for YClampedGradient {
         self.to[0] + (v * (self.to[1] - self.to[0]))
     }
 
-    fn fill(&self, slice: &mut [f64], _: &dyn ContextProvider) {
-        todo!()
+    fn fill(&self, slice: &mut [f64], context_provider: &dyn ContextProvider) {
+        context_provider.fill_direct(slice, self)
     }
 
     fn min(&self) -> f64 {
@@ -39,6 +40,10 @@ impl DensityFunction for YClampedGradient {
     fn max(&self) -> f64 {
         f64::max(self.to[0], self.to[1])
     }
+
+    fn describe(&self) -> Description<'_> {
+        Description::new("y_clamped_gradient")
+    }
 }
 
 #[cfg(test)]
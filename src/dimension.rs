@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use valence_core::ident::Ident;
+
+use crate::biome::source::BiomeSource;
+
+/// A `dimension_type`, the parts of it worldgen cares about.
+#[derive(Deserialize)]
+pub struct DimensionType {
+    pub min_y: i32,
+    pub height: u32,
+    pub logical_height: u32,
+    pub coordinate_scale: f64,
+    pub has_skylight: bool,
+    pub has_ceiling: bool,
+    pub ultrawarm: bool,
+    pub natural: bool,
+    pub ambient_light: f32,
+    pub fixed_time: Option<i64>,
+    pub infiniburn: String,
+    pub effects: Ident<String>,
+}
+
+/// A `worldgen/world_preset`, the dimensions a new world is created with.
+#[derive(Deserialize)]
+pub struct WorldPreset {
+    pub dimensions: HashMap<Ident<String>, LevelStem>,
+}
+
+#[derive(Deserialize)]
+pub struct LevelStem {
+    #[serde(rename = "type")]
+    pub dimension_type: Ident<String>,
+    pub generator: Generator,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum Generator {
+    #[serde(rename = "minecraft:noise")]
+    Noise {
+        settings: Ident<String>,
+        biome_source: BiomeSource,
+    },
+    #[serde(rename = "minecraft:flat")]
+    Flat { settings: serde_json::Value },
+    #[serde(rename = "minecraft:debug")]
+    Debug {},
+}
+
+#[cfg(test)]
+mod test {
+    use crate::biome::source::BiomeSource;
+    use crate::dimension::{DimensionType, Generator, WorldPreset};
+
+    #[test]
+    fn parse_world_preset_and_dimension_type() {
+        let preset: WorldPreset = serde_json::from_str(
+            r#"{
+              "dimensions": {
+                "minecraft:overworld": {
+                  "type": "minecraft:overworld",
+                  "generator": {
+                    "type": "minecraft:noise",
+                    "biome_source": {
+                      "type": "minecraft:multi_noise",
+                      "preset": "minecraft:overworld"
+                    },
+                    "settings": "minecraft:overworld"
+                  }
+                },
+                "minecraft:the_end": {
+                  "type": "minecraft:the_end",
+                  "generator": {
+                    "type": "minecraft:noise",
+                    "biome_source": {
+                      "type": "minecraft:the_end"
+                    },
+                    "settings": "minecraft:end"
+                  }
+                }
+              }
+            }"#,
+        )
+        .expect("should be a valid world preset");
+
+        let end = preset
+            .dimensions
+            .iter()
+            .find(|(id, _)| id.as_str() == "minecraft:the_end")
+            .map(|(_, stem)| stem)
+            .expect("should contain the end");
+        assert!(matches!(
+            &end.generator,
+            Generator::Noise {
+                biome_source: BiomeSource::TheEnd {},
+                ..
+            }
+        ));
+
+        let dimension_type: DimensionType = serde_json::from_str(
+            r##"{
+              "ambient_light": 0.0,
+              "bed_works": true,
+              "coordinate_scale": 1.0,
+              "effects": "minecraft:overworld",
+              "has_ceiling": false,
+              "has_raids": true,
+              "has_skylight": true,
+              "height": 384,
+              "infiniburn": "#minecraft:infiniburn_overworld",
+              "logical_height": 384,
+              "min_y": -64,
+              "monster_spawn_block_light_limit": 0,
+              "monster_spawn_light_level": {
+                "type": "minecraft:uniform",
+                "value": { "max_inclusive": 7, "min_inclusive": 0 }
+              },
+              "natural": true,
+              "piglin_safe": false,
+              "respawn_anchor_works": false,
+              "ultrawarm": false
+            }"##,
+        )
+        .expect("should be a valid dimension type");
+        assert_eq!(-64, dimension_type.min_y);
+        assert_eq!(None, dimension_type.fixed_time);
+    }
+}
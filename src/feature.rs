@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use valence_core::ident::Ident;
+
+/// A `worldgen/configured_feature`. Features are not placed yet, so their
+/// configuration is kept as is.
+#[derive(Deserialize)]
+pub struct ConfiguredFeature {
+    #[serde(rename = "type")]
+    pub kind: Ident<String>,
+    pub config: serde_json::Value,
+}
+
+/// A configured feature either referenced by id or defined inline.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ConfiguredFeatureRef {
+    Reference(Ident<String>),
+    Inline(Box<ConfiguredFeature>),
+}
+
+/// A `worldgen/placed_feature`.
+#[derive(Deserialize)]
+pub struct PlacedFeature {
+    pub feature: ConfiguredFeatureRef,
+    pub placement: Vec<serde_json::Value>,
+}
+
+/// A `worldgen/configured_carver`. Carvers do not run yet, so their
+/// configuration is kept as is.
+#[derive(Deserialize)]
+pub struct ConfiguredCarver {
+    #[serde(rename = "type")]
+    pub kind: Ident<String>,
+    pub config: serde_json::Value,
+}
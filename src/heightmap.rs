@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use valence_block::BlockState;
+
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Heightmap {
+    #[serde(rename = "WORLD_SURFACE_WG")]
+    WorldSurfaceWg,
+    #[serde(rename = "WORLD_SURFACE")]
+    WorldSurface,
+    #[serde(rename = "OCEAN_FLOOR_WG")]
+    OceanFloorWg,
+    #[serde(rename = "OCEAN_FLOOR")]
+    OceanFloor,
+    #[serde(rename = "MOTION_BLOCKING")]
+    MotionBlocking,
+    #[serde(rename = "MOTION_BLOCKING_NO_LEAVES")]
+    MotionBlockingNoLeaves,
+}
+
+impl Heightmap {
+    /// Whether the heightmap counts `state` as its top block.
+    ///
+    /// Only noise generated blocks are considered: solid default blocks,
+    /// fluids and air. Blocking motion is approximated by being neither air
+    /// nor a liquid.
+    pub fn is_opaque(&self, state: BlockState) -> bool {
+        match self {
+            Heightmap::WorldSurfaceWg | Heightmap::WorldSurface => !state.is_air(),
+            Heightmap::OceanFloorWg | Heightmap::OceanFloor => {
+                !state.is_air() && !state.is_liquid()
+            }
+            Heightmap::MotionBlocking | Heightmap::MotionBlockingNoLeaves => !state.is_air(),
+        }
+    }
+}
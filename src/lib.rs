@@ -2,12 +2,17 @@
 #![feature(portable_simd)]
 extern crate core;
 
-mod biome;
+pub mod biome;
+pub mod chunk_generator;
 pub mod density_function;
+pub mod dimension;
+pub mod feature;
+pub mod heightmap;
 pub mod noise;
 pub mod random;
 pub mod registry;
 pub mod spline;
+pub mod structure;
 mod surface;
 
 #[cfg(test)]
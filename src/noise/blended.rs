@@ -0,0 +1,115 @@
+use std::simd::f64x4;
+
+use crate::noise::perlin::PerlinNoise;
+use crate::noise::{lerp, wrap};
+use crate::random::RandomSource;
+
+/// The pre 1.18 terrain noise, still used as `minecraft:old_blended_noise`.
+pub struct BlendedNoise {
+    min_limit_noise: PerlinNoise,
+    max_limit_noise: PerlinNoise,
+    main_noise: PerlinNoise,
+    xz_multiplier: f64,
+    y_multiplier: f64,
+    xz_factor: f64,
+    y_factor: f64,
+    smear_scale_multiplier: f64,
+    max: f64,
+}
+
+impl BlendedNoise {
+    pub fn new(
+        r: &mut dyn RandomSource,
+        xz_scale: f64,
+        y_scale: f64,
+        xz_factor: f64,
+        y_factor: f64,
+        smear_scale_multiplier: f64,
+    ) -> eyre::Result<Self> {
+        let min_limit_noise = PerlinNoise::new_legacy_blended(r, -15)?;
+        let max_limit_noise = PerlinNoise::new_legacy_blended(r, -15)?;
+        let main_noise = PerlinNoise::new_legacy_blended(r, -7)?;
+
+        let y_multiplier = 684.412 * y_scale;
+
+        Ok(Self {
+            max: min_limit_noise.max_broken_value(y_multiplier),
+            min_limit_noise,
+            max_limit_noise,
+            main_noise,
+            xz_multiplier: 684.412 * xz_scale,
+            y_multiplier,
+            xz_factor,
+            y_factor,
+            smear_scale_multiplier,
+        })
+    }
+
+    pub fn get_value(&self, x: i32, y: i32, z: i32) -> f64 {
+        let xyz = f64x4::from_array([
+            x as f64 * self.xz_multiplier,
+            y as f64 * self.y_multiplier,
+            z as f64 * self.xz_multiplier,
+            0.0,
+        ]);
+        let [_, limit_y, _, _] = *xyz.as_array();
+        let main_xyz =
+            xyz / f64x4::from_array([self.xz_factor, self.y_factor, self.xz_factor, 1.0]);
+        let [_, main_y, _, _] = *main_xyz.as_array();
+
+        let limit_smear = self.y_multiplier * self.smear_scale_multiplier;
+        let main_smear = limit_smear / self.y_factor;
+
+        let mut main = 0.0;
+        let mut factor = 1.0;
+        for i in 0..8 {
+            if let Some(noise) = self.main_noise.octave_noise(i) {
+                main += noise.noise_with_y_scale(
+                    wrap(main_xyz * f64x4::splat(factor)),
+                    main_smear * factor,
+                    main_y * factor,
+                ) / factor;
+            }
+            factor /= 2.0;
+        }
+
+        let delta = (main / 10.0 + 1.0) / 2.0;
+        let only_max = delta >= 1.0;
+        let only_min = delta <= 0.0;
+
+        let mut min_limit = 0.0;
+        let mut max_limit = 0.0;
+        let mut factor = 1.0;
+        for i in 0..16 {
+            let input = wrap(xyz * f64x4::splat(factor));
+            let y_scale = limit_smear * factor;
+            let y_max = limit_y * factor;
+
+            if !only_max {
+                if let Some(noise) = self.min_limit_noise.octave_noise(i) {
+                    min_limit += noise.noise_with_y_scale(input, y_scale, y_max) / factor;
+                }
+            }
+            if !only_min {
+                if let Some(noise) = self.max_limit_noise.octave_noise(i) {
+                    max_limit += noise.noise_with_y_scale(input, y_scale, y_max) / factor;
+                }
+            }
+            factor /= 2.0;
+        }
+
+        let value = if delta < 0.0 {
+            min_limit / 512.0
+        } else if delta > 1.0 {
+            max_limit / 512.0
+        } else {
+            lerp(delta, min_limit / 512.0, max_limit / 512.0)
+        };
+
+        value / 128.0
+    }
+
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+}
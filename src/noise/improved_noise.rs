@@ -1,7 +1,8 @@
 use std::f64;
+use std::iter::zip;
 use std::simd::{f64x4, i32x4, StdFloat};
 
-use crate::noise::{grad_dot, lerp3, smooth_step};
+use crate::noise::{grad_dot, grad_dot_x4, lerp3, lerp3_x4, smooth_step};
 use crate::random::RandomSource;
 
 const SIZE: usize = 256;
@@ -33,16 +34,34 @@ impl ImprovedNoise {
         let xyz = self.xyz_origin + xyz;
         let ijk = xyz.floor().cast::<i32>();
         let abc = xyz - (ijk.cast::<f64>());
-        self.sample_and_lerp(ijk, abc)
+        self.sample_and_lerp(ijk, abc, abc)
+    }
+
+    /// Legacy sampling used by blended noise, which quantizes the y offset
+    /// fed into the gradients while still interpolating along the real y.
+    pub fn noise_with_y_scale(&self, xyz: f64x4, y_scale: f64, y_max: f64) -> f64 {
+        let xyz = self.xyz_origin + xyz;
+        let ijk = xyz.floor().cast::<i32>();
+        let abc = xyz - (ijk.cast::<f64>());
+
+        let [_, b, _, _] = *abc.as_array();
+        let y_offset = if y_scale != 0.0 {
+            let y = if y_max >= 0.0 && y_max < b { y_max } else { b };
+            (y / y_scale + 1.0E-7_f32 as f64).floor() as i32 as f64 * y_scale
+        } else {
+            0.0
+        };
+
+        self.sample_and_lerp(ijk, abc - f64x4::from_array([0.0, y_offset, 0.0, 0.0]), abc)
     }
 
     fn p(&self, idx: usize) -> i32 {
         (self.points[idx % SIZE] as usize % SIZE) as i32
     }
 
-    fn sample_and_lerp(&self, ijk: i32x4, abc: f64x4) -> f64 {
-        let [i, j, k, _] = *ijk.as_array();
-
+    /// The gradient indices of the 8 corners of the cell at `i, j, k`, in
+    /// the order [`lerp3`](crate::noise::lerp3) takes them.
+    fn corners(&self, i: i32, j: i32, k: i32) -> [usize; 8] {
         let i2 = self.p(i as usize);
         let j2 = self.p((i + 1) as usize);
         let k2 = self.p((i2 + j) as usize);
@@ -50,38 +69,69 @@ impl ImprovedNoise {
         let i1 = self.p((j2 + j) as usize);
         let j1 = self.p((j2 + j + 1) as usize);
 
-        let d0 = grad_dot(self.p((k2 + k) as usize) as usize, abc);
-        let d1 = grad_dot(
+        [
+            self.p((k2 + k) as usize) as usize,
             self.p((i1 + k) as usize) as usize,
-            abc + f64x4::from_array([-1.0, 0.0, 0.0, 0.0]),
-        );
-        let d2 = grad_dot(
             self.p((l + k) as usize) as usize,
-            abc + f64x4::from_array([0.0, -1.0, 0.0, 0.0]),
-        );
-        let d3 = grad_dot(
             self.p((j1 + k) as usize) as usize,
-            abc + f64x4::from_array([-1.0, -1.0, 0.0, 0.0]),
-        );
-        let d4 = grad_dot(
             self.p((k2 + k + 1) as usize) as usize,
-            abc + f64x4::from_array([0.0, 0.0, -1.0, 0.0]),
-        );
-        let d5 = grad_dot(
             self.p((i1 + k + 1) as usize) as usize,
-            abc + f64x4::from_array([-1.0, 0.0, -1.0, 0.0]),
-        );
-        let d6 = grad_dot(
             self.p((l + k + 1) as usize) as usize,
-            abc + f64x4::from_array([0.0, -1.0, -1.0, 0.0]),
-        );
-        let d7 = grad_dot(
             self.p((j1 + k + 1) as usize) as usize,
-            abc + f64x4::splat(-1.0),
-        );
+        ]
+    }
 
-        let [d8, d9, d10, _] = *smooth_step(abc).as_array();
+    fn sample_and_lerp(&self, ijk: i32x4, abc: f64x4, abc_lerp: f64x4) -> f64 {
+        let [i, j, k, _] = *ijk.as_array();
+        let corners = self.corners(i, j, k);
+
+        let [d0, d1, d2, d3, d4, d5, d6, d7] = [0, 1, 2, 3, 4, 5, 6, 7]
+            .map(|c| grad_dot(corners[c], abc + f64x4::from_array(CORNERS[c])));
+
+        let [d8, d9, d10, _] = *smooth_step(abc_lerp).as_array();
 
         lerp3(d8, d9, d10, d0, d1, d2, d3, d4, d5, d6, d7)
     }
+
+    /// [`noise`](Self::noise) of 4 positions, given per axis with one
+    /// position per lane.
+    pub fn noise_x4(&self, xyz: [f64x4; 3]) -> f64x4 {
+        let origin = *self.xyz_origin.as_array();
+        let xyz = [0, 1, 2].map(|axis| f64x4::splat(origin[axis]) + xyz[axis]);
+        let ijk = xyz.map(|v| v.floor().cast::<i32>());
+        let abc = [0, 1, 2].map(|axis| xyz[axis] - ijk[axis].cast::<f64>());
+
+        let mut corners = [[0; 4]; 8];
+        for lane in 0..4 {
+            let [i, j, k] = ijk.map(|v| v[lane]);
+            for (corner, idx) in zip(&mut corners, self.corners(i, j, k)) {
+                corner[lane] = idx;
+            }
+        }
+
+        let d = [0, 1, 2, 3, 4, 5, 6, 7].map(|c| {
+            let [x, y, z, w] = CORNERS[c];
+            let abc = [
+                abc[0] + f64x4::splat(x),
+                abc[1] + f64x4::splat(y),
+                abc[2] + f64x4::splat(z),
+            ];
+            grad_dot_x4(corners[c], abc, w)
+        });
+
+        lerp3_x4(abc.map(smooth_step), d)
+    }
 }
+
+/// The offsets `sample_and_lerp` adds to the position in the cell for each
+/// corner, including the unused fourth lane.
+const CORNERS: [[f64; 4]; 8] = [
+    [0.0, 0.0, 0.0, 0.0],
+    [-1.0, 0.0, 0.0, 0.0],
+    [0.0, -1.0, 0.0, 0.0],
+    [-1.0, -1.0, 0.0, 0.0],
+    [0.0, 0.0, -1.0, 0.0],
+    [-1.0, 0.0, -1.0, 0.0],
+    [0.0, -1.0, -1.0, 0.0],
+    [-1.0, -1.0, -1.0, -1.0],
+];
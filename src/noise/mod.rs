@@ -1,14 +1,21 @@
+use std::iter::zip;
 use std::simd::{f64x2, f64x4, i32x4, SimdFloat, StdFloat};
 
+pub mod blended;
 pub mod deserialize;
 mod improved_noise;
-mod noise_router;
+pub mod noise_router;
 pub mod normal;
 mod perlin;
+pub mod perlin_simplex;
+pub mod simplex;
 
 #[cfg(test)]
 mod test;
 
+/// Random values a skipped octave would have consumed.
+const SKIPPED_OCTAVE_RANDOM_CALLS: usize = 262;
+
 fn wrap(xyz: f64x4) -> f64x4 {
     xyz - f64x4::floor(xyz / f64x4::splat(3.3554432E7) + f64x4::splat(0.5))
         * f64x4::splat(3.3554432E7)
@@ -41,6 +48,31 @@ fn grad_dot(grad_idx: usize, abc: f64x4) -> f64 {
     (GRADIENTS[grad_idx & (GRADIENTS.len() - 1)].cast::<f64>() * abc).reduce_sum()
 }
 
+/// [`grad_dot`] of 4 positions given per axis, with one gradient per lane.
+/// `w` is the value of the fourth lane [`grad_dot`] would get.
+fn grad_dot_x4(grad_idx: [usize; 4], [a, b, c]: [f64x4; 3], w: f64) -> f64x4 {
+    let g = grad_idx.map(|i| GRADIENTS[i & (GRADIENTS.len() - 1)].cast::<f64>());
+    let [gx, gy, gz] = [0, 1, 2].map(|axis| f64x4::from_array(g.map(|g| g[axis])));
+    // summed in the order of `reduce_sum`, the fourth lane only affects the
+    // sign of zeros
+    gx * a + gy * b + gz * c + f64x4::splat(0.0 * w)
+}
+
+/// Evaluates `f` for every position of `xyz` into `values`, 4 positions at a
+/// time with one axis per vector.
+fn batched(xyz: &[f64x4], values: &mut [f64], f: impl Fn([f64x4; 3]) -> f64x4) {
+    for (xyz, values) in zip(xyz.chunks(4), values.chunks_mut(4)) {
+        let mut axes = [f64x4::splat(0.0); 3];
+        for (lane, pos) in xyz.iter().enumerate() {
+            for (axis, v) in axes.iter_mut().enumerate() {
+                v[lane] = pos[axis];
+            }
+        }
+
+        values.copy_from_slice(&f(axes).as_array()[..values.len()]);
+    }
+}
+
 fn lerp(t: f64, u0: f64, u1: f64) -> f64 {
     u0 + t * (u1 - u0)
 }
@@ -59,6 +91,20 @@ fn lerp2(s: f64, t: f64, v00: f64, v10: f64, v01: f64, v11: f64) -> f64 {
     lerp(t, u0, u1)
 }
 
+fn lerp_x4(t: f64x4, u0: f64x4, u1: f64x4) -> f64x4 {
+    u0 + t * (u1 - u0)
+}
+
+/// [`lerp3`] of 4 positions, with the corners in the same order.
+fn lerp3_x4([r, s, t]: [f64x4; 3], v: [f64x4; 8]) -> f64x4 {
+    let lerp2 = |v00, v10, v01, v11| lerp_x4(s, lerp_x4(r, v00, v10), lerp_x4(r, v01, v11));
+    lerp_x4(
+        t,
+        lerp2(v[0], v[1], v[2], v[3]),
+        lerp2(v[4], v[5], v[6], v[7]),
+    )
+}
+
 fn lerp3(
     r: f64,
     s: f64,
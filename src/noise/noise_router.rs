@@ -1,19 +1,62 @@
-use crate::density_function::deserialize::DensityFunctionTree;
+use crate::density_function::beardifier::Beardifier;
+use crate::density_function::compile::CompileContext;
+use crate::density_function::DensityFunction;
+use crate::noise::deserialize::NoiseRouterBlueprint;
+use crate::random::random_state::RandomState;
 
-pub struct NoiseRouterBlueprint {
-    barrier: DensityFunctionTree,
-    continents: DensityFunctionTree,
-    depth: DensityFunctionTree,
-    erosion: DensityFunctionTree,
-    final_density: DensityFunctionTree,
-    fluid_level_floodedness: DensityFunctionTree,
-    fluid_level_spread: DensityFunctionTree,
-    initial_density_without_jaggedness: DensityFunctionTree,
-    lava: DensityFunctionTree,
-    ridges: DensityFunctionTree,
-    temperature: DensityFunctionTree,
-    vegetation: DensityFunctionTree,
-    vein_gap: DensityFunctionTree,
-    vein_ridged: DensityFunctionTree,
-    vein_toggle: DensityFunctionTree,
+pub struct NoiseRouter {
+    pub barrier: Box<dyn DensityFunction>,
+    pub continents: Box<dyn DensityFunction>,
+    pub depth: Box<dyn DensityFunction>,
+    pub erosion: Box<dyn DensityFunction>,
+    pub final_density: Box<dyn DensityFunction>,
+    pub fluid_level_floodedness: Box<dyn DensityFunction>,
+    pub fluid_level_spread: Box<dyn DensityFunction>,
+    pub initial_density_without_jaggedness: Box<dyn DensityFunction>,
+    pub lava: Box<dyn DensityFunction>,
+    pub ridges: Box<dyn DensityFunction>,
+    pub temperature: Box<dyn DensityFunction>,
+    pub vegetation: Box<dyn DensityFunction>,
+    pub vein_gap: Box<dyn DensityFunction>,
+    pub vein_ridged: Box<dyn DensityFunction>,
+    pub vein_toggle: Box<dyn DensityFunction>,
+}
+
+impl NoiseRouterBlueprint {
+    pub fn compile(&self, random_state: &RandomState) -> eyre::Result<NoiseRouter> {
+        self.compile_in(&mut CompileContext::new(random_state))
+    }
+
+    /// Compiles the router of a chunk, `minecraft:beardifier` adapts the
+    /// terrain to the structures in `beardifier` like in vanilla's
+    /// `NoiseChunk`.
+    pub fn compile_for_chunk(
+        &self,
+        random_state: &RandomState,
+        beardifier: Beardifier,
+    ) -> eyre::Result<NoiseRouter> {
+        self.compile_in(&mut CompileContext::new(random_state).with_beardifier(beardifier))
+    }
+
+    fn compile_in(&self, ctx: &mut CompileContext) -> eyre::Result<NoiseRouter> {
+        Ok(NoiseRouter {
+            barrier: self.barrier.compile_in(ctx)?,
+            continents: self.continents.compile_in(ctx)?,
+            depth: self.depth.compile_in(ctx)?,
+            erosion: self.erosion.compile_in(ctx)?,
+            final_density: self.final_density.compile_in(ctx)?,
+            fluid_level_floodedness: self.fluid_level_floodedness.compile_in(ctx)?,
+            fluid_level_spread: self.fluid_level_spread.compile_in(ctx)?,
+            initial_density_without_jaggedness: self
+                .initial_density_without_jaggedness
+                .compile_in(ctx)?,
+            lava: self.lava.compile_in(ctx)?,
+            ridges: self.ridges.compile_in(ctx)?,
+            temperature: self.temperature.compile_in(ctx)?,
+            vegetation: self.vegetation.compile_in(ctx)?,
+            vein_gap: self.vein_gap.compile_in(ctx)?,
+            vein_ridged: self.vein_ridged.compile_in(ctx)?,
+            vein_toggle: self.vein_toggle.compile_in(ctx)?,
+        })
+    }
 }
@@ -1,5 +1,6 @@
 use std::simd::f64x4;
 
+use crate::noise::batched;
 use crate::noise::deserialize::NoiseParameters;
 use crate::noise::perlin::PerlinNoise;
 use crate::random::{Kind, RandomSource};
@@ -14,7 +15,10 @@ pub struct NormalNoise {
 }
 
 impl NormalNoise {
-    pub fn new(r: &mut dyn RandomSource, noise_data: &NoiseParameters) -> NormalNoise {
+    pub fn new(
+        r: &mut dyn RandomSource,
+        noise_data: &NoiseParameters,
+    ) -> eyre::Result<NormalNoise> {
         let (first, second) = match r.kind() {
             Kind::Xoroshiro => (
                 PerlinNoise::new(r, noise_data.first_octave, noise_data.amplitudes.as_slice()),
@@ -25,12 +29,12 @@ impl NormalNoise {
                     r,
                     noise_data.first_octave,
                     noise_data.amplitudes.as_slice(),
-                ),
+                )?,
                 PerlinNoise::new_legacy_nether(
                     r,
                     noise_data.first_octave,
                     noise_data.amplitudes.as_slice(),
-                ),
+                )?,
             ),
         };
 
@@ -48,12 +52,12 @@ impl NormalNoise {
         let value_factor = (1.0 / 6.0) / expected_deviation;
         let max_value = (first.max() + second.max()) * value_factor;
 
-        Self {
+        Ok(Self {
             value_factor,
             max: max_value,
             first,
             second,
-        }
+        })
     }
 
     pub fn get_value(&self, xyz: f64x4) -> f64 {
@@ -61,6 +65,21 @@ impl NormalNoise {
             * self.value_factor
     }
 
+    /// [`get_value`](Self::get_value) of 4 positions, given per axis with one
+    /// position per lane.
+    pub fn get_value_x4(&self, xyz: [f64x4; 3]) -> f64x4 {
+        (self.first.get_value_x4(xyz)
+            + self
+                .second
+                .get_value_x4(xyz.map(|v| v * f64x4::splat(INPUT_FACTOR))))
+            * f64x4::splat(self.value_factor)
+    }
+
+    /// [`get_value`](Self::get_value) of every position of `xyz`.
+    pub fn get_values(&self, xyz: &[f64x4], values: &mut [f64]) {
+        batched(xyz, values, |xyz| self.get_value_x4(xyz))
+    }
+
     pub fn max(&self) -> f64 {
         self.max
     }
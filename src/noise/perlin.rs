@@ -1,7 +1,7 @@
 use std::simd::{f64x2, f64x4};
 
 use crate::noise::improved_noise::ImprovedNoise;
-use crate::noise::wrap;
+use crate::noise::{wrap, SKIPPED_OCTAVE_RANDOM_CALLS};
 use crate::random::RandomSource;
 
 pub struct PerlinNoise {
@@ -48,11 +48,74 @@ impl PerlinNoise {
         r: &mut dyn RandomSource,
         first_octave: i32,
         amplitudes: &[f64],
-    ) -> Self {
-        dbg!(r.kind());
-        dbg!(first_octave);
-        dbg!(amplitudes);
-        todo!()
+    ) -> eyre::Result<Self> {
+        Self::new_legacy(r, first_octave, amplitudes)
+    }
+
+    /// Octaves `-15..=0` or `-7..=0` with a full amplitude each.
+    pub fn new_legacy_blended(r: &mut dyn RandomSource, first_octave: i32) -> eyre::Result<Self> {
+        Self::new_legacy(
+            r,
+            first_octave,
+            vec![1.0; -first_octave as usize + 1].as_slice(),
+        )
+    }
+
+    fn new_legacy(
+        r: &mut dyn RandomSource,
+        first_octave: i32,
+        amplitudes: &[f64],
+    ) -> eyre::Result<Self> {
+        let len = amplitudes.len() as i32;
+        let zero_octave_idx = -first_octave;
+        if zero_octave_idx < len - 1 {
+            return Err(eyre::eyre!(
+                "positive octaves are not supported by legacy perlin noise"
+            ));
+        }
+
+        let mut noise_levels = (0..len).map(|_| None).collect::<Vec<_>>();
+
+        let zero_octave = ImprovedNoise::new(r);
+        if zero_octave_idx < len && amplitudes[zero_octave_idx as usize] != 0.0 {
+            noise_levels[zero_octave_idx as usize] = Some(zero_octave);
+        }
+
+        for i in (0..zero_octave_idx).rev() {
+            if i < len && amplitudes[i as usize] != 0.0 {
+                noise_levels[i as usize] = Some(ImprovedNoise::new(r));
+            } else {
+                r.consume(SKIPPED_OCTAVE_RANDOM_CALLS);
+            }
+        }
+
+        let lowest_freq_input_factor = 2.0f64.powi(-zero_octave_idx);
+        let lowest_freq_value_factor = 2.0f64.powi((amplitudes.len() - 1) as i32)
+            / (2.0f64.powi(amplitudes.len() as i32) - 1.0);
+
+        Ok(Self {
+            max: Self::edge_value(2.0, amplitudes, lowest_freq_value_factor),
+            noise_levels,
+            lowest_freq_input_factor,
+            lowest_freq_value_factor,
+            amplitudes: Vec::from(amplitudes),
+        })
+    }
+
+    /// Octaves are indexed starting at the highest frequency.
+    pub fn octave_noise(&self, i: usize) -> Option<&ImprovedNoise> {
+        self.noise_levels
+            .len()
+            .checked_sub(1 + i)
+            .and_then(|i| self.noise_levels[i].as_ref())
+    }
+
+    pub fn max_broken_value(&self, y_multiplier: f64) -> f64 {
+        Self::edge_value(
+            y_multiplier + 2.0,
+            self.amplitudes.as_slice(),
+            self.lowest_freq_value_factor,
+        )
     }
 
     pub fn max(&self) -> f64 {
@@ -79,6 +142,30 @@ impl PerlinNoise {
             .sum()
     }
 
+    /// [`get_value`](Self::get_value) of 4 positions, given per axis with one
+    /// position per lane.
+    pub fn get_value_x4(&self, xyz: [f64x4; 3]) -> f64x4 {
+        let mut factors =
+            f64x2::from_array([self.lowest_freq_input_factor, self.lowest_freq_value_factor]);
+
+        self.noise_levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| {
+                let amp = self.amplitudes[i] * factors.as_array()[1];
+                let input = xyz.map(|v| wrap(v * f64x4::splat(factors.as_array()[0])));
+                factors *= f64x2::from_array([2.0, 0.5]);
+
+                f64x4::splat(amp)
+                    * match level {
+                        None => f64x4::splat(0.0),
+                        Some(level) => level.noise_x4(input),
+                    }
+            })
+            // starts at -0.0 like `Iterator::sum` of `f64`
+            .fold(f64x4::splat(-0.0), |sum, v| sum + v)
+    }
+
     fn edge_value(x: f64, amplitudes: &[f64], lowest_freq_value_factor: f64) -> f64 {
         let mut value_factor = lowest_freq_value_factor;
 
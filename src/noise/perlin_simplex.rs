@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+use crate::noise::simplex::SimplexNoise;
+use crate::noise::SKIPPED_OCTAVE_RANDOM_CALLS;
+use crate::random::legacy::LegacyRandom;
+use crate::random::RandomSource;
+
+pub struct PerlinSimplexNoise {
+    noise_levels: Vec<Option<SimplexNoise>>,
+    lowest_freq_input_factor: f64,
+    highest_freq_value_factor: f64,
+}
+
+impl PerlinSimplexNoise {
+    pub fn new(r: &mut dyn RandomSource, octaves: &[i32]) -> eyre::Result<Self> {
+        let octaves = octaves.iter().copied().collect::<BTreeSet<_>>();
+        let (first, last) = match (octaves.first(), octaves.last()) {
+            (Some(&first), Some(&last)) => (-first, last),
+            _ => return Err(eyre::eyre!("need some octaves")),
+        };
+
+        let total = first + last + 1;
+        if total < 1 {
+            return Err(eyre::eyre!("total number of octaves needs to be >= 1"));
+        }
+
+        let mut noise_levels = (0..total).map(|_| None).collect::<Vec<_>>();
+
+        let zero_octave = SimplexNoise::new(r);
+        let zero_octave_value = zero_octave.get_value_3d(
+            zero_octave.xyz_origin[0],
+            zero_octave.xyz_origin[1],
+            zero_octave.xyz_origin[2],
+        );
+
+        if last >= 0 && last < total && octaves.contains(&0) {
+            noise_levels[last as usize] = Some(zero_octave);
+        }
+
+        for i in (last + 1)..total {
+            if i >= 0 && octaves.contains(&(last - i)) {
+                noise_levels[i as usize] = Some(SimplexNoise::new(r));
+            } else {
+                r.consume(SKIPPED_OCTAVE_RANDOM_CALLS);
+            }
+        }
+
+        if last > 0 {
+            let mut r = LegacyRandom::new((zero_octave_value * 9.223372E18_f32 as f64) as i64);
+
+            for i in (0..last).rev() {
+                if i < total && octaves.contains(&(last - i)) {
+                    noise_levels[i as usize] = Some(SimplexNoise::new(r.as_mut()));
+                } else {
+                    r.consume(SKIPPED_OCTAVE_RANDOM_CALLS);
+                }
+            }
+        }
+
+        Ok(Self {
+            noise_levels,
+            lowest_freq_input_factor: 2.0f64.powi(last),
+            highest_freq_value_factor: 1.0 / (2.0f64.powi(total) - 1.0),
+        })
+    }
+
+    pub fn get_value(&self, x: f64, y: f64, use_noise_offsets: bool) -> f64 {
+        let mut input_factor = self.lowest_freq_input_factor;
+        let mut value_factor = self.highest_freq_value_factor;
+
+        self.noise_levels
+            .iter()
+            .map(|level| {
+                let v = match level {
+                    None => 0.0,
+                    Some(level) => {
+                        let (xo, yo) = match use_noise_offsets {
+                            true => (level.xyz_origin[0], level.xyz_origin[1]),
+                            false => (0.0, 0.0),
+                        };
+                        level.get_value_2d(x * input_factor + xo, y * input_factor + yo)
+                            * value_factor
+                    }
+                };
+
+                input_factor /= 2.0;
+                value_factor *= 2.0;
+                v
+            })
+            .sum()
+    }
+}
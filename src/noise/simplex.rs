@@ -0,0 +1,132 @@
+use std::simd::{f64x4, i32x4, SimdFloat};
+
+use crate::noise::GRADIENTS;
+use crate::random::RandomSource;
+
+const SIZE: usize = 256;
+
+const SQRT_3: f64 = 1.7320508075688772;
+const F2: f64 = 0.5 * (SQRT_3 - 1.0);
+const G2: f64 = (3.0 - SQRT_3) / 6.0;
+const F3: f64 = 0.3333333333333333;
+const G3: f64 = 0.16666666666666666;
+
+pub struct SimplexNoise {
+    points: [u8; SIZE],
+    pub xyz_origin: f64x4,
+}
+
+impl SimplexNoise {
+    pub fn new(r: &mut dyn RandomSource) -> Self {
+        let xyz_origin = f64x4::from_array([r.next_f64(), r.next_f64(), r.next_f64(), 0.0])
+            * f64x4::splat(SIZE as f64);
+
+        let mut points = [0; SIZE];
+        for (i, s) in points.iter_mut().enumerate() {
+            *s = i as u8;
+        }
+
+        for i in 0..points.len() {
+            let j = i + r.next_i32_bound((SIZE - i) as i32) as usize;
+            (points[i], points[j]) = (points[j], points[i]);
+        }
+
+        Self { points, xyz_origin }
+    }
+
+    fn p(&self, idx: i32) -> i32 {
+        self.points[(idx & 0xFF) as usize] as i32
+    }
+
+    /// Evaluates the contribution of up to four simplex corners at once, one
+    /// corner per lane. Lanes with a negative falloff do not contribute.
+    fn corner_noise(grad_idx: i32x4, x: f64x4, y: f64x4, z: f64x4, r: f64) -> f64 {
+        let t = (f64x4::splat(r) - x * x - y * y - z * z).simd_max(f64x4::splat(0.0));
+
+        let grad = grad_idx
+            .to_array()
+            .map(|i| GRADIENTS[i as usize].cast::<f64>().to_array());
+        let gx = f64x4::from_array(grad.map(|g| g[0]));
+        let gy = f64x4::from_array(grad.map(|g| g[1]));
+        let gz = f64x4::from_array(grad.map(|g| g[2]));
+
+        let t2 = t * t;
+        let n = t2 * t2 * (gx * x + gy * y + gz * z);
+        let [n0, n1, n2, n3] = n.to_array();
+
+        // keep the summation order of the scalar implementation
+        n0 + n1 + n2 + n3
+    }
+
+    pub fn get_value_2d(&self, x: f64, y: f64) -> f64 {
+        let s = (x + y) * F2;
+        let i = (x + s).floor() as i32;
+        let j = (y + s).floor() as i32;
+
+        let t = (i + j) as f64 * G2;
+        let x0 = x - (i as f64 - t);
+        let y0 = y - (j as f64 - t);
+
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+        let ii = i & 0xFF;
+        let jj = j & 0xFF;
+        let gi0 = self.p(ii + self.p(jj)) % 12;
+        let gi1 = self.p(ii + i1 + self.p(jj + j1)) % 12;
+        let gi2 = self.p(ii + 1 + self.p(jj + 1)) % 12;
+
+        // the fourth lane is out of range and therefore masked
+        let xs = f64x4::from_array([x0, x0 - i1 as f64 + G2, x0 - 1.0 + 2.0 * G2, 1.0]);
+        let ys = f64x4::from_array([y0, y0 - j1 as f64 + G2, y0 - 1.0 + 2.0 * G2, 1.0]);
+
+        70.0 * Self::corner_noise(
+            i32x4::from_array([gi0, gi1, gi2, 0]),
+            xs,
+            ys,
+            f64x4::splat(0.0),
+            0.5,
+        )
+    }
+
+    pub fn get_value_3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        let s = (x + y + z) * F3;
+        let i = (x + s).floor() as i32;
+        let j = (y + s).floor() as i32;
+        let k = (z + s).floor() as i32;
+
+        let t = (i + j + k) as f64 * G3;
+        let x0 = x - (i as f64 - t);
+        let y0 = y - (j as f64 - t);
+        let z0 = z - (k as f64 - t);
+
+        let ([i1, j1, k1], [i2, j2, k2]) = if x0 >= y0 {
+            if y0 >= z0 {
+                ([1, 0, 0], [1, 1, 0])
+            } else if x0 >= z0 {
+                ([1, 0, 0], [1, 0, 1])
+            } else {
+                ([0, 0, 1], [1, 0, 1])
+            }
+        } else if y0 < z0 {
+            ([0, 0, 1], [0, 1, 1])
+        } else if x0 < z0 {
+            ([0, 1, 0], [0, 1, 1])
+        } else {
+            ([0, 1, 0], [1, 1, 0])
+        };
+
+        let ii = i & 0xFF;
+        let jj = j & 0xFF;
+        let kk = k & 0xFF;
+        let gi0 = self.p(ii + self.p(jj + self.p(kk))) % 12;
+        let gi1 = self.p(ii + i1 + self.p(jj + j1 + self.p(kk + k1))) % 12;
+        let gi2 = self.p(ii + i2 + self.p(jj + j2 + self.p(kk + k2))) % 12;
+        let gi3 = self.p(ii + 1 + self.p(jj + 1 + self.p(kk + 1))) % 12;
+
+        let xs = f64x4::from_array([x0, x0 - i1 as f64 + G3, x0 - i2 as f64 + F3, x0 - 1.0 + 0.5]);
+        let ys = f64x4::from_array([y0, y0 - j1 as f64 + G3, y0 - j2 as f64 + F3, y0 - 1.0 + 0.5]);
+        let zs = f64x4::from_array([z0, z0 - k1 as f64 + G3, z0 - k2 as f64 + F3, z0 - 1.0 + 0.5]);
+
+        32.0 * Self::corner_noise(i32x4::from_array([gi0, gi1, gi2, gi3]), xs, ys, zs, 0.6)
+    }
+}
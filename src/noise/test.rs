@@ -1,8 +1,13 @@
+use std::iter::zip;
 use std::simd::f64x4;
 
 use crate::noise::deserialize::NoiseParameters;
 use crate::noise::normal::NormalNoise;
+use crate::noise::perlin_simplex::PerlinSimplexNoise;
+use crate::noise::simplex::SimplexNoise;
+use crate::random::legacy::LegacyRandom;
 use crate::random::xoroshiro::XoroshiroRandom;
+use crate::random::RandomSource;
 
 const SEED: i64 = 0x786b544d6f473757_i64;
 
@@ -16,7 +21,7 @@ fn normal_noise_test() {
     };
 
     let mut r = XoroshiroRandom::new(SEED);
-    let noise = NormalNoise::new(r.as_mut(), &noise_data);
+    let noise = NormalNoise::new(r.as_mut(), &noise_data).unwrap();
 
     for (i, &s) in sample.iter().enumerate() {
         let (x, y, z) = ((r.next_f64() - 0.5) * 10000_f64, (r.next_f64() - 0.5) * 10000_f64, (r.next_f64() - 0.5) * 10000_f64);
@@ -42,3 +47,219 @@ fn normal_noise_test() {
 
    System.out.println(b.toString());
 */
+
+/// Straight port of vanilla's scalar `SimplexNoise` the vectorised
+/// implementation has to match bit for bit.
+struct ScalarSimplexNoise {
+    p: [i32; 256],
+}
+
+impl ScalarSimplexNoise {
+    const GRADIENT: [[i32; 3]; 16] = [
+        [1, 1, 0],
+        [-1, 1, 0],
+        [1, -1, 0],
+        [-1, -1, 0],
+        [1, 0, 1],
+        [-1, 0, 1],
+        [1, 0, -1],
+        [-1, 0, -1],
+        [0, 1, 1],
+        [0, -1, 1],
+        [0, 1, -1],
+        [0, -1, -1],
+        [1, 1, 0],
+        [0, -1, 1],
+        [-1, 1, 0],
+        [0, -1, -1],
+    ];
+
+    fn new(r: &mut dyn RandomSource) -> Self {
+        r.next_f64();
+        r.next_f64();
+        r.next_f64();
+
+        let mut p = [0; 256];
+        for (i, v) in p.iter_mut().enumerate() {
+            *v = i as i32;
+        }
+        for i in 0..256 {
+            let j = r.next_i32_bound(256 - i as i32) as usize;
+            p.swap(i, i + j);
+        }
+        Self { p }
+    }
+
+    fn p(&self, i: i32) -> i32 {
+        self.p[(i & 0xFF) as usize]
+    }
+
+    fn corner(i: i32, x: f64, y: f64, z: f64, r: f64) -> f64 {
+        let mut e = r - x * x - y * y - z * z;
+        if e < 0.0 {
+            0.0
+        } else {
+            let g = Self::GRADIENT[i as usize];
+            e *= e;
+            e * e * (g[0] as f64 * x + g[1] as f64 * y + g[2] as f64 * z)
+        }
+    }
+
+    fn get_value_2d(&self, x: f64, y: f64) -> f64 {
+        let sqrt_3 = 3.0_f64.sqrt();
+        let f2 = 0.5 * (sqrt_3 - 1.0);
+        let g2 = (3.0 - sqrt_3) / 6.0;
+
+        let d = (x + y) * f2;
+        let i = (x + d).floor() as i32;
+        let j = (y + d).floor() as i32;
+        let e = (i + j) as f64 * g2;
+        let h = x - (i as f64 - e);
+        let k = y - (j as f64 - e);
+        let (l, m) = if h > k { (1, 0) } else { (0, 1) };
+        let n = h - l as f64 + g2;
+        let o = k - m as f64 + g2;
+        let p = h - 1.0 + 2.0 * g2;
+        let q = k - 1.0 + 2.0 * g2;
+        let r = i & 0xFF;
+        let s = j & 0xFF;
+        let t = self.p(r + self.p(s)) % 12;
+        let u = self.p(r + l + self.p(s + m)) % 12;
+        let v = self.p(r + 1 + self.p(s + 1)) % 12;
+        70.0 * (Self::corner(t, h, k, 0.0, 0.5)
+            + Self::corner(u, n, o, 0.0, 0.5)
+            + Self::corner(v, p, q, 0.0, 0.5))
+    }
+
+    fn get_value_3d(&self, x: f64, y: f64, z: f64) -> f64 {
+        let f = (x + y + z) * 0.3333333333333333;
+        let i = (x + f).floor() as i32;
+        let j = (y + f).floor() as i32;
+        let k = (z + f).floor() as i32;
+        let h = (i + j + k) as f64 * 0.16666666666666666;
+        let o = x - (i as f64 - h);
+        let p = y - (j as f64 - h);
+        let q = z - (k as f64 - h);
+        let (r, s, t, u, v, w) = if o >= p {
+            if p >= q {
+                (1, 0, 0, 1, 1, 0)
+            } else if o >= q {
+                (1, 0, 0, 1, 0, 1)
+            } else {
+                (0, 0, 1, 1, 0, 1)
+            }
+        } else if p < q {
+            (0, 0, 1, 0, 1, 1)
+        } else if o < q {
+            (0, 1, 0, 0, 1, 1)
+        } else {
+            (0, 1, 0, 1, 1, 0)
+        };
+        let aj = i & 0xFF;
+        let ak = j & 0xFF;
+        let al = k & 0xFF;
+        let am = self.p(aj + self.p(ak + self.p(al))) % 12;
+        let an = self.p(aj + r + self.p(ak + s + self.p(al + t))) % 12;
+        let ao = self.p(aj + u + self.p(ak + v + self.p(al + w))) % 12;
+        let ap = self.p(aj + 1 + self.p(ak + 1 + self.p(al + 1))) % 12;
+        32.0 * (Self::corner(am, o, p, q, 0.6)
+            + Self::corner(
+                an,
+                o - r as f64 + 0.16666666666666666,
+                p - s as f64 + 0.16666666666666666,
+                q - t as f64 + 0.16666666666666666,
+                0.6,
+            )
+            + Self::corner(
+                ao,
+                o - u as f64 + 0.3333333333333333,
+                p - v as f64 + 0.3333333333333333,
+                q - w as f64 + 0.3333333333333333,
+                0.6,
+            )
+            + Self::corner(ap, o - 1.0 + 0.5, p - 1.0 + 0.5, q - 1.0 + 0.5, 0.6))
+    }
+}
+
+#[test]
+#[rustfmt::skip]
+fn simplex_noise_test() {
+    let noise = SimplexNoise::new(XoroshiroRandom::new(SEED).as_mut());
+    let reference = ScalarSimplexNoise::new(XoroshiroRandom::new(SEED).as_mut());
+
+    let mut r = XoroshiroRandom::new(SEED);
+    for i in 0..1024 {
+        let (x, y, z) = ((r.next_f64() - 0.5) * 10000_f64, (r.next_f64() - 0.5) * 10000_f64, (r.next_f64() - 0.5) * 10000_f64);
+
+        let (v, s) = (noise.get_value_2d(x, y), reference.get_value_2d(x, y));
+        assert_eq!(s, v, "[{i}] 2d noise({x}, {y})");
+
+        let (v, s) = (noise.get_value_3d(x, y, z), reference.get_value_3d(x, y, z));
+        assert_eq!(s, v, "[{i}] 3d noise({x}, {y}, {z})");
+    }
+}
+
+#[test]
+fn perlin_simplex_noise_test() {
+    let noise = PerlinSimplexNoise::new(XoroshiroRandom::new(SEED).as_mut(), &[-2, -1, 0])
+        .expect("octaves should be valid");
+    let again = PerlinSimplexNoise::new(XoroshiroRandom::new(SEED).as_mut(), &[0, -1, -2])
+        .expect("octaves should be valid");
+
+    let mut r = XoroshiroRandom::new(SEED);
+    for _ in 0..128 {
+        let (x, z) = (
+            (r.next_f64() - 0.5) * 10000_f64,
+            (r.next_f64() - 0.5) * 10000_f64,
+        );
+        let v = noise.get_value(x, z, false);
+        assert_eq!(v, again.get_value(x, z, false));
+        assert!(v.abs() <= 1.0, "{v} out of range");
+    }
+
+    assert!(PerlinSimplexNoise::new(XoroshiroRandom::new(SEED).as_mut(), &[]).is_err());
+}
+
+#[test]
+fn normal_noise_batch_test() {
+    let noise_data = NoiseParameters {
+        first_octave: -7,
+        amplitudes: vec![1.0, -0.5, 0.0, 2.0, 1.0],
+    };
+
+    for mut r in [XoroshiroRandom::new(SEED), LegacyRandom::new(SEED)] {
+        let noise = NormalNoise::new(r.as_mut(), &noise_data).unwrap();
+
+        // not a multiple of 4, with lattice points and positions beyond the wrap
+        let mut xyz = (0..130)
+            .map(|_| {
+                let mut v = || (r.next_f64() - 0.5) * 10000_f64;
+                f64x4::from_array([v(), v(), v(), 0.0])
+            })
+            .collect::<Vec<_>>();
+        xyz.push(f64x4::from_array([0.0, -0.0, 128.0, 0.0]));
+        xyz.push(f64x4::from_array([4.0E9, -3.0E10, 1.0E12, 0.0]));
+
+        let mut values = vec![0.0; xyz.len()];
+        noise.get_values(&xyz, &mut values);
+        for (i, (&xyz, v)) in zip(&xyz, values).enumerate() {
+            let s = noise.get_value(xyz);
+            assert_eq!(
+                s.to_bits(),
+                v.to_bits(),
+                "[{i}] {s} =?= {v} = noise({xyz:?})"
+            );
+        }
+    }
+}
+
+#[test]
+fn legacy_positive_octaves_test() {
+    let noise_data = NoiseParameters {
+        first_octave: -1,
+        amplitudes: vec![1.0, 1.0, 1.0],
+    };
+
+    assert!(NormalNoise::new(LegacyRandom::new(SEED).as_mut(), &noise_data).is_err());
+    assert!(NormalNoise::new(XoroshiroRandom::new(SEED).as_mut(), &noise_data).is_ok());
+}
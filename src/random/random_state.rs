@@ -1,35 +1,92 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
-use crate::noise::deserialize::NoiseGeneratorSettings;
-use crate::random::PositionalRandomFactory;
+use valence_core::ident::Ident;
+
+use crate::density_function::compile::Backend;
+use crate::density_function::DensityFunction;
+use crate::noise::deserialize::{NoiseGeneratorSettings, NoiseSettings};
+use crate::noise::normal::NormalNoise;
+use crate::random::{Kind, PositionalRandomFactory};
 use crate::registry::Registry;
 use crate::surface::SurfaceSystem;
 
+/// How deep density functions may be nested by default, references included.
+pub const DEFAULT_MAX_COMPILE_DEPTH: usize = 256;
+
 pub struct RandomState {
     pub(crate) random: Box<dyn PositionalRandomFactory>,
     pub(crate) seed: i64,
     pub(crate) registry: Arc<dyn Registry>,
+    pub(crate) noise_settings: NoiseSettings,
     pub(crate) aquifer_random: Box<dyn PositionalRandomFactory>,
     pub(crate) ore_random: Box<dyn PositionalRandomFactory>,
-    //pub(crate) noise_instance_cache: Map<Ident<String>, NormalNoise>                         // TODO: are cached noise instances useful? - Probably yes
+    pub(crate) noise_instance_cache: RwLock<HashMap<Ident<String>, Arc<NormalNoise>>>,
+    /// Density functions compiled from a `Reference`, shared by every
+    /// reference to the same id.
+    pub(crate) compiled_cache: RwLock<HashMap<Ident<String>, Arc<dyn DensityFunction>>>,
     //pub(crate) random_factories_cache: Map<Ident<String>, Box<dyn PositionalRandomFactory>>  // TODO: are cached random factories useful? - I am not sure
     pub(crate) surface_system: SurfaceSystem,
+    pub(crate) max_compile_depth: usize,
+    pub(crate) simplify: bool,
+    pub(crate) backend: Backend,
 }
 
 impl RandomState {
     pub fn new(settings: &NoiseGeneratorSettings, registry: Arc<dyn Registry>, seed: i64) -> Self {
-        let random = settings
-            .random_source_kind
-            .new_instance(seed)
-            .fork_positional();
+        Self::from_parts(
+            settings.noise_settings,
+            &settings.random_source_kind,
+            registry,
+            seed,
+        )
+    }
+
+    /// A random state without full noise generator settings, e.g. to compile
+    /// a density function on its own.
+    pub(crate) fn from_parts(
+        noise_settings: NoiseSettings,
+        kind: &Kind,
+        registry: Arc<dyn Registry>,
+        seed: i64,
+    ) -> Self {
+        let random = kind.new_instance(seed).fork_positional();
 
         Self {
             aquifer_random: random.with_hash_of("aquifer").fork_positional(),
             ore_random: random.with_hash_of("ore").fork_positional(),
             surface_system: SurfaceSystem,
+            noise_settings,
             random,
             seed,
             registry,
+            max_compile_depth: DEFAULT_MAX_COMPILE_DEPTH,
+            simplify: true,
+            backend: Backend::default(),
+            noise_instance_cache: Default::default(),
+            compiled_cache: Default::default(),
         }
     }
+
+    /// Limits how deep density functions compiled with this state may be
+    /// nested, so malformed datapacks fail to compile instead of overflowing
+    /// the stack.
+    pub fn with_max_compile_depth(mut self, depth: usize) -> Self {
+        self.max_compile_depth = depth;
+        self
+    }
+
+    /// Whether density functions are simplified while compiling, e.g. by
+    /// folding constants. Enabled by default, the results are the same either
+    /// way.
+    pub fn with_simplification(mut self, simplify: bool) -> Self {
+        self.simplify = simplify;
+        self
+    }
+
+    /// How density functions compiled with this state are evaluated.
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
 }
@@ -0,0 +1,379 @@
+use std::fs::File;
+use std::io::Read;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use eyre::eyre;
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::registry::mc_meta::McMetaRegistry;
+use crate::registry::validate::{self, ValidationReport};
+use crate::registry::Registry;
+
+/// `pack_format`s [`Datapack::open`] accepts, 1.19.2 up to 1.21.8, the last
+/// release with a plain `pack_format`. Every density function type up to
+/// `minecraft:invert` and `minecraft:find_top_surface` is parsed, as are the
+/// biome carvers with and without carving steps, use
+/// [`Datapack::open_with_pack_formats`] for other versions.
+pub const SUPPORTED_PACK_FORMATS: RangeInclusive<i32> = 10..=81;
+
+enum Archive {
+    Directory(PathBuf),
+    Zip(PathBuf, Mutex<ZipArchive<File>>),
+    Static(&'static [(&'static str, &'static [u8])]),
+}
+
+/// Where the files of a pack live, either a directory or a `.zip` file.
+/// Paths are relative to the pack root, e.g.
+/// `data/minecraft/worldgen/noise/ore_gap.json`.
+pub struct PackSource {
+    name: String,
+    archive: Archive,
+}
+
+impl PackSource {
+    pub fn directory<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        Self {
+            name: pack_name(path),
+            archive: Archive::Directory(path.to_path_buf()),
+        }
+    }
+
+    pub fn zip<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        Ok(Self {
+            name: pack_name(path),
+            archive: Archive::Zip(path.to_path_buf(), Mutex::new(open_zip(path)?)),
+        })
+    }
+
+    /// Opens a `.zip` file again, to read what was written to it since.
+    /// Directories are read from disk every time and files compiled into the
+    /// binary never change.
+    pub fn reopen(&self) -> eyre::Result<()> {
+        if let Archive::Zip(path, archive) = &self.archive {
+            let reopened = open_zip(path)?;
+            *archive.lock().unwrap_or_else(PoisonError::into_inner) = reopened;
+        }
+        Ok(())
+    }
+
+    /// Files compiled into the binary, sorted by name.
+    pub fn embedded(name: &str, files: &'static [(&'static str, &'static [u8])]) -> Self {
+        Self {
+            name: name.to_string(),
+            archive: Archive::Static(files),
+        }
+    }
+
+    /// The file or directory name of the pack.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        match &self.archive {
+            Archive::Directory(root) => root.join(path).is_file(),
+            Archive::Zip(_, archive) => match (archive.lock(), path.to_str()) {
+                (Ok(mut archive), Some(path)) => archive.by_name(path).is_ok(),
+                _ => false,
+            },
+            Archive::Static(files) => Self::find_static(files, path).is_some(),
+        }
+    }
+
+    pub fn read(&self, path: &Path) -> eyre::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        match &self.archive {
+            Archive::Directory(root) => {
+                let path = root.join(path);
+                File::open(&path)
+                    .and_then(|mut f| f.read_to_end(&mut bytes))
+                    .map_err(|e| eyre!("unable to open {}, Error: {e}", path.display()))?;
+            }
+            Archive::Zip(_, archive) => {
+                let mut archive = archive
+                    .lock()
+                    .map_err(|e| eyre!("unable to acquire lock on {}, {e}", self.name))?;
+                let name = path
+                    .to_str()
+                    .ok_or_else(|| eyre!("{} is not a valid zip entry name", path.display()))?;
+                archive
+                    .by_name(name)
+                    .map_err(|e| eyre!("unable to open {}/{name}, Error: {e}", self.name))?
+                    .read_to_end(&mut bytes)
+                    .map_err(|e| eyre!("unable to read {}/{name}, Error: {e}", self.name))?;
+            }
+            Archive::Static(files) => match Self::find_static(files, path) {
+                Some(file) => bytes.extend_from_slice(file),
+                None => return Err(eyre!("{} has no file {}", self.name, path.display())),
+            },
+        }
+
+        Ok(bytes)
+    }
+
+    /// The paths of all files in the pack, sorted.
+    pub fn files(&self) -> eyre::Result<Vec<String>> {
+        let mut files = match &self.archive {
+            Archive::Directory(root) => {
+                let mut files = vec![];
+                list_directory(root, root, &mut files)?;
+                files
+            }
+            Archive::Zip(_, archive) => archive
+                .lock()
+                .map_err(|e| eyre!("unable to acquire lock on {}, {e}", self.name))?
+                .file_names()
+                .filter(|name| !name.ends_with('/'))
+                .map(str::to_string)
+                .collect(),
+            Archive::Static(files) => files.iter().map(|(name, _)| name.to_string()).collect(),
+        };
+        files.sort();
+
+        Ok(files)
+    }
+
+    fn find_static(
+        files: &'static [(&'static str, &'static [u8])],
+        path: &Path,
+    ) -> Option<&'static [u8]> {
+        let path = path.to_str()?;
+        files
+            .binary_search_by_key(&path, |(name, _)| name)
+            .ok()
+            .map(|i| files[i].1)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PackMeta {
+    pub pack: PackInfo,
+}
+
+#[derive(Deserialize)]
+pub struct PackInfo {
+    pub pack_format: i32,
+    #[serde(default)]
+    pub description: serde_json::Value,
+}
+
+/// A datapack with a validated `pack.mcmeta`.
+pub struct Datapack {
+    source: PackSource,
+    meta: PackMeta,
+}
+
+impl Datapack {
+    /// Opens the datapack at `path`, a directory or a `.zip` file.
+    pub fn open<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        Self::open_with_pack_formats(path, SUPPORTED_PACK_FORMATS)
+    }
+
+    /// Like [`open`](Self::open), accepting the `pack_format`s in `supported`
+    /// instead of [`SUPPORTED_PACK_FORMATS`].
+    pub fn open_with_pack_formats<P: AsRef<Path>>(
+        path: P,
+        supported: RangeInclusive<i32>,
+    ) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let source = match path.is_dir() {
+            true => PackSource::directory(path),
+            false => PackSource::zip(path)?,
+        };
+
+        let bytes = source.read(Path::new("pack.mcmeta"))?;
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let meta: PackMeta = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            eyre!(
+                "unable to deserialize {}/pack.mcmeta::{} - {e}",
+                source.name(),
+                e.path()
+            )
+        })?;
+
+        if !supported.contains(&meta.pack.pack_format) {
+            return Err(eyre!(
+                "{} has pack_format {}, supported are {} to {}",
+                source.name(),
+                meta.pack.pack_format,
+                supported.start(),
+                supported.end()
+            ));
+        }
+
+        Ok(Self { source, meta })
+    }
+
+    /// Opens every datapack in the `datapacks` folder of the world at
+    /// `world`, ordered by name. Entries that are neither directories nor
+    /// `.zip` files are skipped.
+    pub fn open_world<P: AsRef<Path>>(world: P) -> eyre::Result<Vec<Self>> {
+        let folder = world.as_ref().join("datapacks");
+        if !folder.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut paths = folder
+            .read_dir()
+            .and_then(|entries| {
+                entries
+                    .map(|e| e.map(|e| e.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(|e| eyre!("unable to list {}, Error: {e}", folder.display()))?;
+        paths.retain(|p| p.is_dir() || p.extension().is_some_and(|e| e == "zip"));
+        paths.sort();
+
+        paths.into_iter().map(Self::open).collect()
+    }
+
+    pub fn name(&self) -> &str {
+        self.source.name()
+    }
+
+    pub fn meta(&self) -> &PackMeta {
+        &self.meta
+    }
+
+    /// Loads every worldgen entry and tag of this pack and resolves their
+    /// references, with `base` providing what the pack builds upon, usually
+    /// vanilla. See [`ValidationReport`].
+    pub fn validate(self, base: Option<Box<dyn Registry>>) -> eyre::Result<ValidationReport> {
+        let files = self.source.files()?;
+        let name = self.name().to_string();
+        Ok(validate::validate(
+            name,
+            &files,
+            Box::new(self.into_registry()),
+            base,
+        ))
+    }
+
+    /// A registry serving the contents of this pack.
+    pub fn into_registry(self) -> McMetaRegistry {
+        McMetaRegistry::from_source(self.source, None)
+    }
+}
+
+fn list_directory(root: &Path, dir: &Path, files: &mut Vec<String>) -> eyre::Result<()> {
+    let entries = dir
+        .read_dir()
+        .map_err(|e| eyre!("unable to list {}, Error: {e}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| eyre!("unable to list {}, Error: {e}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            list_directory(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let components = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>();
+            files.push(components.join("/"));
+        }
+    }
+
+    Ok(())
+}
+
+fn open_zip(path: &Path) -> eyre::Result<ZipArchive<File>> {
+    File::open(path)
+        .map_err(|e| eyre!("unable to open {}, Error: {e}", path.display()))
+        .and_then(|f| {
+            ZipArchive::new(f).map_err(|e| eyre!("unable to read {}, Error: {e}", path.display()))
+        })
+}
+
+fn pack_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use valence_core::ident;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::registry::datapack::Datapack;
+    use crate::registry::Registry;
+    use crate::test::TempDir;
+
+    fn zipped_pack(dir: &TempDir, name: &str, pack_format: i32) -> PathBuf {
+        zipped_pack_with(dir, name, pack_format, "0.5")
+    }
+
+    /// A pack in `dir` with density function `test:a` being `a`.
+    fn zipped_pack_with(dir: &TempDir, name: &str, pack_format: i32, a: &str) -> PathBuf {
+        let path = dir.path().join(format!("{name}.zip"));
+
+        let mut zip = ZipWriter::new(File::create(&path).expect("should create zip"));
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("pack.mcmeta", options).unwrap();
+        write!(
+            zip,
+            r#"{{"pack": {{"pack_format": {pack_format}, "description": "test"}}}}"#
+        )
+        .unwrap();
+        zip.start_file("data/test/worldgen/density_function/a.json", options)
+            .unwrap();
+        write!(zip, "{a}").unwrap();
+        zip.finish().expect("should write zip");
+
+        path
+    }
+
+    #[test]
+    fn zipped_datapack_test() {
+        let dir = TempDir::new("datapack");
+        let pack =
+            Datapack::open(zipped_pack(&dir, "valid", 15)).expect("should be a valid datapack");
+        assert_eq!("valid.zip", pack.name());
+        assert_eq!(15, pack.meta().pack.pack_format);
+
+        let registry = pack.into_registry();
+        match registry.density_function(&ident!("test:a")).as_deref() {
+            Ok(DensityFunctionTree::Constant(v)) => assert_eq!(0.5, *v),
+            _ => panic!("test:a should be a constant"),
+        }
+        assert!(registry.density_function(&ident!("test:b")).is_err());
+
+        assert!(Datapack::open(zipped_pack(&dir, "outdated", 4)).is_err());
+        assert!(Datapack::open(zipped_pack(&dir, "newer", 48)).is_ok());
+        assert!(Datapack::open(zipped_pack(&dir, "future", 1000)).is_err());
+        assert!(
+            Datapack::open_with_pack_formats(zipped_pack(&dir, "future", 1000), 10..=1000).is_ok()
+        );
+    }
+
+    #[test]
+    fn zipped_datapack_reload_test() {
+        let dir = TempDir::new("datapack_reload");
+        let path = zipped_pack_with(&dir, "reload", 15, "0.5");
+        let registry = Datapack::open(&path)
+            .expect("should be a valid datapack")
+            .into_registry();
+        let a = || match registry.density_function(&ident!("test:a")).as_deref() {
+            Ok(DensityFunctionTree::Constant(v)) => *v,
+            _ => panic!("test:a should be a constant"),
+        };
+        assert_eq!(0.5, a());
+
+        zipped_pack_with(&dir, "reload", 15, "0.25");
+        assert_eq!(0.5, a());
+        registry.reload().expect("zip should open again");
+        assert_eq!(0.25, a());
+    }
+}
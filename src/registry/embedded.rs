@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use valence_core::ident::Ident;
+
+use crate::registry::datapack::PackSource;
+use crate::registry::mc_meta::McMetaRegistry;
+use crate::registry::tag::TagFile;
+use crate::registry::{AnyEntry, EntryParser, Registry};
+
+include!(concat!(env!("OUT_DIR"), "/embedded.rs"));
+
+/// Vanilla's worldgen data as it was in the mcmeta checkout the crate was
+/// built against, no files are needed at runtime.
+pub struct EmbeddedRegistry(McMetaRegistry);
+
+impl EmbeddedRegistry {
+    pub fn new() -> Self {
+        Self(McMetaRegistry::from_source(
+            PackSource::embedded("vanilla", EMBEDDED_FILES),
+            None,
+        ))
+    }
+}
+
+impl Default for EmbeddedRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry for EmbeddedRegistry {
+    fn root_registry(&self) -> &dyn Registry {
+        self
+    }
+
+    fn contains(&self, registry: &str, id: &Ident<&str>) -> bool {
+        self.0.contains(registry, id)
+    }
+
+    fn entry(
+        &self,
+        registry: &'static str,
+        id: &Ident<&str>,
+        parse: EntryParser,
+    ) -> eyre::Result<AnyEntry> {
+        self.0.entry(registry, id, parse)
+    }
+
+    fn tag_files(&self, registry: &str, id: &Ident<&str>) -> eyre::Result<Vec<Arc<TagFile>>> {
+        self.0.tag_files(registry, id)
+    }
+
+    fn reload(&self) -> eyre::Result<()> {
+        self.0.reload()
+    }
+}
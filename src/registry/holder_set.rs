@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Deserializer};
+use valence_core::ident::Ident;
+
+use crate::registry::Registry;
+
+/// A set of registry entries, either listed directly or referenced by a tag
+/// written as `#namespace:path`.
+#[derive(Clone, Debug)]
+pub enum HolderSet {
+    Direct(Vec<Ident<String>>),
+    Tag(Ident<String>),
+}
+
+impl HolderSet {
+    /// Whether `id` is part of this set, tags are looked up in `kind` of
+    /// `registry`.
+    pub fn contains<R: Registry + ?Sized>(
+        &self,
+        registry: &R,
+        kind: &str,
+        id: &Ident<&str>,
+    ) -> eyre::Result<bool> {
+        match self {
+            HolderSet::Direct(ids) => Ok(ids.iter().any(|i| i.as_str() == id.as_str())),
+            HolderSet::Tag(tag) => Ok(registry
+                .tag(kind, &tag.as_str_ident())?
+                .iter()
+                .any(|i| i.as_str() == id.as_str())),
+        }
+    }
+
+    /// Every id in this set, tags are looked up in `kind` of `registry`.
+    pub fn resolve<R: Registry + ?Sized>(
+        &self,
+        registry: &R,
+        kind: &str,
+    ) -> eyre::Result<HashSet<Ident<String>>> {
+        match self {
+            HolderSet::Direct(ids) => Ok(ids.iter().cloned().collect()),
+            HolderSet::Tag(tag) => registry.tag(kind, &tag.as_str_ident()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HolderSet {
+    fn deserialize<D>(d: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            List(Vec<Ident<String>>),
+            Single(String),
+        }
+
+        match Raw::deserialize(d)? {
+            Raw::List(ids) => Ok(HolderSet::Direct(ids)),
+            Raw::Single(s) => match s.strip_prefix('#') {
+                Some(tag) => Ident::new(tag)
+                    .map(|i| HolderSet::Tag(i.to_string_ident()))
+                    .map_err(serde::de::Error::custom),
+                None => Ident::new(s)
+                    .map(|i| HolderSet::Direct(vec![i.to_string_ident()]))
+                    .map_err(serde::de::Error::custom),
+            },
+        }
+    }
+}
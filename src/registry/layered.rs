@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use eyre::eyre;
+use valence_core::ident::Ident;
+
+use crate::registry::datapack::Datapack;
+use crate::registry::tag::TagFile;
+use crate::registry::{AnyEntry, EntryParser, Registry};
+
+struct Layer {
+    name: String,
+    registry: Box<dyn Registry>,
+}
+
+/// Stacks registries like vanilla stacks datapacks: each entry is served by
+/// the last layer that has it, so later layers override earlier ones.
+///
+/// A typical stack is vanilla mcmeta, followed by datapacks, which share the
+/// mcmeta layout and can be loaded with a
+/// [`McMetaRegistry`](crate::registry::mc_meta::McMetaRegistry) each,
+/// followed by in-memory overrides.
+///
+/// Tags are merged instead, every layer appends to the tag unless its file
+/// sets `replace`.
+#[derive(Default)]
+pub struct LayeredRegistry {
+    layers: Vec<Layer>,
+    sources: RwLock<HashMap<(String, String), String>>,
+}
+
+impl LayeredRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `registry` on top of all existing layers.
+    pub fn with_layer<S: Into<String>>(mut self, name: S, registry: Box<dyn Registry>) -> Self {
+        self.push_layer(name, registry);
+        self
+    }
+
+    /// Adds `registry` on top of all existing layers.
+    pub fn push_layer<S: Into<String>>(&mut self, name: S, registry: Box<dyn Registry>) {
+        self.layers.push(Layer {
+            name: name.into(),
+            registry,
+        });
+    }
+
+    /// Adds `datapacks` on top of all existing layers, in order. Each layer is
+    /// named after its pack.
+    pub fn with_datapacks(mut self, datapacks: Vec<Datapack>) -> Self {
+        for pack in datapacks {
+            let name = pack.name().to_string();
+            self.push_layer(name, Box::new(pack.into_registry()));
+        }
+        self
+    }
+
+    /// The names of all layers, lowest precedence first.
+    pub fn layers(&self) -> impl Iterator<Item = &str> {
+        self.layers.iter().map(|l| l.name.as_str())
+    }
+
+    /// The name of the layer entry `id` of `registry` was loaded from, `None`
+    /// if it has not been loaded yet.
+    pub fn source(&self, registry: &str, id: &Ident<&str>) -> Option<String> {
+        self.sources
+            .read()
+            .ok()?
+            .get(&(registry.to_string(), id.to_string()))
+            .cloned()
+    }
+
+    fn top_layer(&self, registry: &str, id: &Ident<&str>) -> Option<&Layer> {
+        self.layers
+            .iter()
+            .rev()
+            .find(|l| l.registry.contains(registry, id))
+    }
+}
+
+impl Registry for LayeredRegistry {
+    fn root_registry(&self) -> &dyn Registry {
+        self
+    }
+
+    fn contains(&self, registry: &str, id: &Ident<&str>) -> bool {
+        self.top_layer(registry, id).is_some()
+    }
+
+    fn entry(
+        &self,
+        registry: &'static str,
+        id: &Ident<&str>,
+        parse: EntryParser,
+    ) -> eyre::Result<AnyEntry> {
+        let Some(layer) = self.top_layer(registry, id) else {
+            return Err(eyre!("{registry} {id} not found in any layer"));
+        };
+
+        let object = layer.registry.entry(registry, id, parse).map_err(|e| {
+            e.wrap_err(format!(
+                "unable to load {registry} {id} from {}",
+                layer.name
+            ))
+        })?;
+
+        match self.sources.write() {
+            Ok(mut sources) => {
+                sources.insert((registry.to_string(), id.to_string()), layer.name.clone());
+                Ok(object)
+            }
+            Err(e) => Err(eyre!("unable to acquire lock on source map, {}", e)),
+        }
+    }
+
+    fn tag_files(&self, registry: &str, id: &Ident<&str>) -> eyre::Result<Vec<Arc<TagFile>>> {
+        let mut files = vec![];
+        for layer in &self.layers {
+            let layer_files = layer
+                .registry
+                .tag_files(registry, id)
+                .map_err(|e| e.wrap_err(format!("unable to load tag #{id} from {}", layer.name)))?;
+            files.extend(layer_files);
+        }
+        Ok(files)
+    }
+
+    fn reload(&self) -> eyre::Result<()> {
+        self.sources
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        for layer in &self.layers {
+            layer.registry.reload()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use valence_core::ident;
+
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::registry::layered::LayeredRegistry;
+    use crate::registry::mc_meta::McMetaRegistry;
+    use crate::registry::{Registry, DENSITY_FUNCTION};
+    use crate::test::TempDir;
+
+    #[test]
+    fn layer_precedence_test() {
+        let dir = TempDir::new("layered");
+        dir.write(&[
+            ("vanilla/data/test/worldgen/density_function/a.json", "1.0"),
+            ("vanilla/data/test/worldgen/density_function/b.json", "2.0"),
+            ("datapack/data/test/worldgen/density_function/b.json", "3.0"),
+            ("datapack/data/test/worldgen/density_function/c.json", "4.0"),
+        ]);
+        let (vanilla, datapack) = (dir.path().join("vanilla"), dir.path().join("datapack"));
+
+        let registry = LayeredRegistry::new()
+            .with_layer(
+                "vanilla",
+                Box::new(McMetaRegistry::new(vanilla.to_str().unwrap(), None)),
+            )
+            .with_layer(
+                "datapack",
+                Box::new(McMetaRegistry::new(datapack.to_str().unwrap(), None)),
+            );
+
+        for (id, value, source) in [
+            (ident!("test:a"), 1.0, "vanilla"),
+            (ident!("test:b"), 3.0, "datapack"),
+            (ident!("test:c"), 4.0, "datapack"),
+        ] {
+            assert_eq!(None, registry.source(DENSITY_FUNCTION, &id));
+            match registry.density_function(&id).as_deref() {
+                Ok(DensityFunctionTree::Constant(v)) => assert_eq!(value, *v),
+                _ => panic!("{id} should be a constant"),
+            }
+            assert_eq!(
+                Some(source.to_string()),
+                registry.source(DENSITY_FUNCTION, &id)
+            );
+        }
+
+        assert!(!registry.contains(DENSITY_FUNCTION, &ident!("test:d")));
+        assert!(registry.density_function(&ident!("test:d")).is_err());
+    }
+}
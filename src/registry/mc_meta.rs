@@ -1,73 +1,63 @@
 use std::collections::HashMap;
-use std::fs::File;
 use std::path::PathBuf;
-use std::str::FromStr;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, PoisonError, RwLock};
 
 use eyre::eyre;
-use serde::de;
 use valence_core::ident::Ident;
 
-use crate::density_function::deserialize::DensityFunctionTree;
-use crate::noise::deserialize::{NoiseGeneratorSettings, NoiseParameters};
-use crate::registry::Registry;
+use crate::registry::datapack::PackSource;
+use crate::registry::tag::{tag_path, TagFile};
+use crate::registry::{AnyEntry, DeserializeError, EntryParser, Registry};
 
-type Cache<T> = RwLock<HashMap<String, Arc<T>>>;
+type Cache = RwLock<HashMap<(&'static str, String), AnyEntry>>;
+type TagCache = RwLock<HashMap<(String, String), Arc<TagFile>>>;
 
 pub struct McMetaRegistry {
     root_registry: Option<Box<dyn Registry>>,
-    mcmeta_root: PathBuf,
+    source: PackSource,
 
-    density_function_cache: Cache<DensityFunctionTree>,
-    noise_cache: Cache<NoiseParameters>,
-    noise_generator_settings_cache: Cache<NoiseGeneratorSettings>,
+    cache: Cache,
+    tags: TagCache,
 }
 
 impl McMetaRegistry {
     pub fn new<S: AsRef<str>>(root_path: S, root_registry: Option<Box<dyn Registry>>) -> Self {
+        Self::from_source(PackSource::directory(root_path.as_ref()), root_registry)
+    }
+
+    /// A registry reading the `data` folder of `source`, which may be a
+    /// datapack directory or `.zip` file as well.
+    pub fn from_source(source: PackSource, root_registry: Option<Box<dyn Registry>>) -> Self {
         Self {
-            mcmeta_root: root_path.as_ref().to_string().into(),
+            source,
             root_registry,
-            ..Default::default()
+            cache: Default::default(),
+            tags: Default::default(),
         }
     }
 
-    fn load_from_file<T>(&self, path: &PathBuf) -> eyre::Result<T>
-    where
-        T: de::DeserializeOwned,
-    {
-        let json_file_path = self.mcmeta_root.join(path);
+    /// The name of the directory or file this registry reads from.
+    pub fn source_name(&self) -> &str {
+        self.source.name()
+    }
 
-        let f = match File::open(json_file_path.clone()) {
-            Ok(f) => f,
-            Err(e) => {
-                return Err(eyre!(
-                    "unable to open {}, Error: {}",
-                    json_file_path.display(),
-                    e
-                ));
-            }
-        };
+    fn load_from_file(&self, path: &PathBuf, parse: EntryParser) -> eyre::Result<AnyEntry> {
+        let bytes = self.source.read(path)?;
 
-        let deserializer = &mut serde_json::Deserializer::from_reader(f);
-        serde_path_to_error::deserialize(deserializer)
-            .map_err(|e| eyre!("unable to deserialize {path:?}::{} - {e}", e.path()))
+        parse(&bytes).map_err(|e| DeserializeError::new(path, e).into())
     }
 
-    fn cached<T, H: FnMut(&Ident<&str>, T) -> eyre::Result<T>>(
+    fn cached(
         &self,
+        registry: &'static str,
         id: &Ident<&str>,
-        map: &Cache<T>,
-        path: &PathBuf,
-        mut hydration_visitor: H,
-    ) -> eyre::Result<Arc<T>>
-    where
-        T: de::DeserializeOwned,
-    {
-        match map.read() {
+        parse: EntryParser,
+    ) -> eyre::Result<AnyEntry> {
+        let key = (registry, id.to_string());
+        match self.cache.read() {
             Ok(map) => {
-                if map.contains_key(id.as_str()) {
-                    return Ok(map.get(id.as_str()).unwrap().clone());
+                if let Some(entry) = map.get(&key) {
+                    return Ok(entry.clone());
                 }
             }
             Err(e) => {
@@ -75,24 +65,39 @@ impl McMetaRegistry {
             }
         }
 
-        return match self.load_from_file(path) {
-            Ok(object) => match map.write() {
-                Ok(mut map) => {
-                    if map.contains_key(id.as_str()) {
-                        return Ok(map.get(id.as_str()).unwrap().clone());
-                    }
-
-                    let arc = Arc::from(match hydration_visitor(id, object) {
-                        Ok(o) => o,
-                        Err(e) => return Err(e),
-                    });
-                    map.insert(id.to_string(), arc.clone());
-                    Ok(arc)
+        let object = self.load_from_file(&McMetaRegistry::data_path(registry, id), parse)?;
+        match self.cache.write() {
+            Ok(mut map) => Ok(map.entry(key).or_insert(object).clone()),
+            Err(e) => Err(eyre!("unable to acquire lock on map, {}", e)),
+        }
+    }
+
+    fn cached_tag(&self, registry: &str, id: &Ident<&str>) -> eyre::Result<Option<Arc<TagFile>>> {
+        let key = (registry.to_string(), id.to_string());
+        match self.tags.read() {
+            Ok(map) => {
+                if let Some(tag) = map.get(&key) {
+                    return Ok(Some(tag.clone()));
                 }
-                Err(e) => Err(eyre!("unable to acquire lock on map, {}", e)),
-            },
-            Err(e) => Err(e),
-        };
+            }
+            Err(e) => {
+                return Err(eyre!("unable to acquire lock on tag map, {}", e));
+            }
+        }
+
+        let path = McMetaRegistry::data_path(&tag_path(registry), id);
+        if !self.source.contains(&path) {
+            return Ok(None);
+        }
+
+        let bytes = self.source.read(&path)?;
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let tag: TagFile = serde_path_to_error::deserialize(deserializer)
+            .map_err(|e| DeserializeError::new(&path, e))?;
+        match self.tags.write() {
+            Ok(mut map) => Ok(Some(map.entry(key).or_insert(Arc::new(tag)).clone())),
+            Err(e) => Err(eyre!("unable to acquire lock on tag map, {}", e)),
+        }
     }
 
     fn data_path<'a>(path: &'a str, tag: &'a Ident<&str>) -> PathBuf {
@@ -102,13 +107,7 @@ impl McMetaRegistry {
 
 impl Default for McMetaRegistry {
     fn default() -> Self {
-        McMetaRegistry {
-            root_registry: None,
-            mcmeta_root: PathBuf::from_str("./mcmeta").unwrap(),
-            density_function_cache: Default::default(),
-            noise_cache: Default::default(),
-            noise_generator_settings_cache: Default::default(),
-        }
+        McMetaRegistry::new("./mcmeta", None)
     }
 }
 
@@ -120,33 +119,37 @@ impl Registry for McMetaRegistry {
         }
     }
 
-    fn density_function(&self, id: &Ident<&str>) -> eyre::Result<Arc<DensityFunctionTree>> {
-        self.cached(
-            id,
-            &self.density_function_cache,
-            &McMetaRegistry::data_path("worldgen/density_function", id),
-            |_, tree| Ok(tree),
-        )
-    }
-
-    fn noise(&self, id: &Ident<&str>) -> eyre::Result<Arc<NoiseParameters>> {
-        self.cached(
-            id,
-            &self.noise_cache,
-            &McMetaRegistry::data_path("worldgen/noise", id),
-            |_, tree| Ok(tree),
-        )
+    fn contains(&self, registry: &str, id: &Ident<&str>) -> bool {
+        self.source
+            .contains(&McMetaRegistry::data_path(registry, id))
     }
 
-    fn noise_generator_settings(
+    fn entry(
         &self,
+        registry: &'static str,
         id: &Ident<&str>,
-    ) -> eyre::Result<Arc<NoiseGeneratorSettings>> {
-        self.cached(
-            id,
-            &self.noise_generator_settings_cache,
-            &McMetaRegistry::data_path("worldgen/noise_settings", id),
-            |_, tree| Ok(tree),
-        )
+        parse: EntryParser,
+    ) -> eyre::Result<AnyEntry> {
+        self.cached(registry, id, parse)
+    }
+
+    fn tag_files(&self, registry: &str, id: &Ident<&str>) -> eyre::Result<Vec<Arc<TagFile>>> {
+        Ok(self.cached_tag(registry, id)?.into_iter().collect())
+    }
+
+    fn reload(&self) -> eyre::Result<()> {
+        self.source.reopen()?;
+        self.cache
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.tags
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        match &self.root_registry {
+            Some(root_registry) => root_registry.reload(),
+            None => Ok(()),
+        }
     }
 }
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use eyre::eyre;
+use valence_core::ident::Ident;
+
+use crate::density_function::deserialize::DensityFunctionTree;
+use crate::noise::deserialize::{NoiseGeneratorSettings, NoiseParameters};
+use crate::registry::tag::TagFile;
+use crate::registry::{AnyEntry, EntryParser, Registry, RegistryEntry};
+
+/// A registry holding only what was inserted into it, for generated content
+/// and tests. Inserting replaces existing entries.
+#[derive(Default)]
+pub struct MemoryRegistry {
+    entries: RwLock<HashMap<(&'static str, String), AnyEntry>>,
+    tags: RwLock<HashMap<(String, String), Arc<TagFile>>>,
+}
+
+impl MemoryRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts `entry` as `id` into the registry of `T`.
+    pub fn insert<T: RegistryEntry>(&self, id: &Ident<&str>, entry: impl Into<Arc<T>>) {
+        let entry: Arc<T> = entry.into();
+        self.entries
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert((T::REGISTRY, id.to_string()), entry);
+    }
+
+    /// Inserts `tag` as tag `id` of `registry`, e.g. [`BIOME`](crate::registry::BIOME).
+    pub fn insert_tag(&self, registry: &str, id: &Ident<&str>, tag: impl Into<Arc<TagFile>>) {
+        self.tags
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert((registry.to_string(), id.to_string()), tag.into());
+    }
+
+    pub fn insert_density_function(
+        &self,
+        id: &Ident<&str>,
+        density_function: impl Into<Arc<DensityFunctionTree>>,
+    ) {
+        self.insert(id, density_function)
+    }
+
+    pub fn insert_noise(&self, id: &Ident<&str>, noise: impl Into<Arc<NoiseParameters>>) {
+        self.insert(id, noise)
+    }
+
+    pub fn insert_noise_generator_settings(
+        &self,
+        id: &Ident<&str>,
+        settings: impl Into<Arc<NoiseGeneratorSettings>>,
+    ) {
+        self.insert(id, settings)
+    }
+
+    pub fn with<T: RegistryEntry>(self, id: &Ident<&str>, entry: impl Into<Arc<T>>) -> Self {
+        self.insert(id, entry);
+        self
+    }
+
+    pub fn with_tag(self, registry: &str, id: &Ident<&str>, tag: impl Into<Arc<TagFile>>) -> Self {
+        self.insert_tag(registry, id, tag);
+        self
+    }
+
+    pub fn with_density_function(
+        self,
+        id: &Ident<&str>,
+        density_function: impl Into<Arc<DensityFunctionTree>>,
+    ) -> Self {
+        self.with(id, density_function)
+    }
+
+    pub fn with_noise(self, id: &Ident<&str>, noise: impl Into<Arc<NoiseParameters>>) -> Self {
+        self.with(id, noise)
+    }
+
+    pub fn with_noise_generator_settings(
+        self,
+        id: &Ident<&str>,
+        settings: impl Into<Arc<NoiseGeneratorSettings>>,
+    ) -> Self {
+        self.with(id, settings)
+    }
+}
+
+impl Registry for MemoryRegistry {
+    fn root_registry(&self) -> &dyn Registry {
+        self
+    }
+
+    fn contains(&self, registry: &str, id: &Ident<&str>) -> bool {
+        self.entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .any(|(r, i)| *r == registry && i == id.as_str())
+    }
+
+    fn entry(
+        &self,
+        registry: &'static str,
+        id: &Ident<&str>,
+        _: EntryParser,
+    ) -> eyre::Result<AnyEntry> {
+        self.entries
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(registry, id.to_string()))
+            .cloned()
+            .ok_or_else(|| eyre!("{registry} {id} was never inserted"))
+    }
+
+    fn tag_files(&self, registry: &str, id: &Ident<&str>) -> eyre::Result<Vec<Arc<TagFile>>> {
+        Ok(self
+            .tags
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(registry.to_string(), id.to_string()))
+            .cloned()
+            .into_iter()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use valence_core::block_pos::BlockPos;
+    use valence_core::ident;
+
+    use crate::density_function::deserialize::DensityFunctionTree;
+    use crate::noise::deserialize::NoiseParameters;
+    use crate::random::random_state::RandomState;
+    use crate::registry::memory::MemoryRegistry;
+    use crate::registry::{Registry, RegistryExt, NOISE};
+    use crate::test::noise_settings;
+
+    #[test]
+    fn memory_registry_test() {
+        let registry = MemoryRegistry::new()
+            .with_noise(&ident!("test:noise"), NoiseParameters::new(-3, vec![1.0]))
+            .with_density_function(
+                &ident!("test:final_density"),
+                serde_json::from_str::<DensityFunctionTree>(
+                    r#"{"type": "minecraft:noise", "noise": "test:noise", "xz_scale": 1, "y_scale": 1}"#,
+                )
+                .expect("should be a valid density function"),
+            );
+        registry.insert_noise_generator_settings(&ident!("test:settings"), noise_settings());
+
+        assert!(registry.contains(NOISE, &ident!("test:noise")));
+        assert!(!registry.contains(NOISE, &ident!("test:final_density")));
+        assert!(registry.noise(&ident!("test:missing")).is_err());
+
+        let registry = Arc::new(registry);
+        let dyn_registry: &dyn Registry = registry.as_ref();
+        let noise = dyn_registry
+            .get::<NoiseParameters>(&ident!("test:noise"))
+            .expect("noise was inserted");
+        assert_eq!(-3, noise.first_octave);
+
+        let settings = registry
+            .noise_generator_settings(&ident!("test:settings"))
+            .expect("settings were inserted");
+        let random_state = RandomState::new(&settings, registry.clone(), 42);
+        let router = settings
+            .noise_router
+            .compile(&random_state)
+            .expect("noise router should compile");
+
+        let values = (0..64)
+            .map(|i| router.final_density.compute(BlockPos::new(i * 3, 0, i * 7)))
+            .collect::<Vec<_>>();
+        assert!(values.iter().all(|v| v.abs() <= router.final_density.max()));
+        assert!(values.iter().any(|v| *v != 0.0));
+    }
+}
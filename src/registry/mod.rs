@@ -1,18 +1,213 @@
+use std::any::{type_name, Any};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use eyre::eyre;
+use serde::de::DeserializeOwned;
 use valence_core::ident::Ident;
 
+use crate::biome::source::MultiNoiseBiomeSourceParameterList;
+use crate::biome::Biome;
 use crate::density_function::deserialize::DensityFunctionTree;
+use crate::dimension::{DimensionType, WorldPreset};
+use crate::feature::{ConfiguredCarver, ConfiguredFeature, PlacedFeature};
 use crate::noise::deserialize::{NoiseGeneratorSettings, NoiseParameters};
+use crate::registry::tag::TagFile;
+use crate::structure::placement::StructureSet;
+use crate::structure::pool::{ProcessorList, TemplatePool};
+use crate::structure::Structure;
 
+pub mod datapack;
+#[cfg(feature = "embedded")]
+pub mod embedded;
+pub mod holder_set;
+pub mod layered;
 pub mod mc_meta;
+pub mod memory;
+pub mod tag;
+pub mod validate;
+
+/// A loaded registry entry of any type.
+pub type AnyEntry = Arc<dyn Any + Send + Sync>;
+
+/// Parses the json of a registry entry, see [`RegistryEntry`].
+pub type EntryParser = fn(&[u8]) -> Result<AnyEntry, serde_path_to_error::Error<serde_json::Error>>;
+
+/// A type stored in a registry, `REGISTRY` is the path of its files below
+/// `data/<namespace>/`.
+pub trait RegistryEntry: DeserializeOwned + Send + Sync + 'static {
+    const REGISTRY: &'static str;
+}
+
+macro_rules! registry_entries {
+    ($($name:ident: $entry:ty = $path:literal,)*) => {
+        /// Every registry with a [`RegistryEntry`] type.
+        pub const REGISTRIES: &[&str] = &[$($name),*];
+
+        $(
+            pub const $name: &str = $path;
+
+            impl RegistryEntry for $entry {
+                const REGISTRY: &'static str = $name;
+            }
+        )*
+    };
+}
+
+registry_entries! {
+    BIOME: Biome = "worldgen/biome",
+    CONFIGURED_CARVER: ConfiguredCarver = "worldgen/configured_carver",
+    CONFIGURED_FEATURE: ConfiguredFeature = "worldgen/configured_feature",
+    DENSITY_FUNCTION: DensityFunctionTree = "worldgen/density_function",
+    DIMENSION_TYPE: DimensionType = "dimension_type",
+    MULTI_NOISE_BIOME_SOURCE_PARAMETER_LIST: MultiNoiseBiomeSourceParameterList =
+        "worldgen/multi_noise_biome_source_parameter_list",
+    NOISE: NoiseParameters = "worldgen/noise",
+    NOISE_GENERATOR_SETTINGS: NoiseGeneratorSettings = "worldgen/noise_settings",
+    PLACED_FEATURE: PlacedFeature = "worldgen/placed_feature",
+    PROCESSOR_LIST: ProcessorList = "worldgen/processor_list",
+    STRUCTURE: Structure = "worldgen/structure",
+    STRUCTURE_SET: StructureSet = "worldgen/structure_set",
+    TEMPLATE_POOL: TemplatePool = "worldgen/template_pool",
+    WORLD_PRESET: WorldPreset = "worldgen/world_preset",
+}
+
+/// A registry file that is not valid json for its entry type.
+#[derive(Debug)]
+pub struct DeserializeError {
+    /// The file, relative to the root of its pack.
+    pub file: PathBuf,
+    /// Where in the file deserializing failed, see [`serde_path_to_error`].
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unable to deserialize {:?}::{} - {}",
+            self.file, self.path, self.message
+        )
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl DeserializeError {
+    pub(crate) fn new(file: &Path, e: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            path: e.path().to_string(),
+            message: e.inner().to_string(),
+        }
+    }
+}
 
 pub trait Registry {
     fn root_registry(&self) -> &dyn Registry;
-    fn density_function(&self, id: &Ident<&str>) -> eyre::Result<Arc<DensityFunctionTree>>;
-    fn noise(&self, id: &Ident<&str>) -> eyre::Result<Arc<NoiseParameters>>;
+    /// Whether this registry has an entry `id` in `registry`, without loading
+    /// it. `registry` is the path below `data/<namespace>/`, e.g.
+    /// [`DENSITY_FUNCTION`].
+    fn contains(&self, registry: &str, id: &Ident<&str>) -> bool;
+    /// Loads entry `id` of `registry`, using `parse` if it has to be read
+    /// from json. Use [`RegistryExt::get`] for typed access.
+    fn entry(
+        &self,
+        registry: &'static str,
+        id: &Ident<&str>,
+        parse: EntryParser,
+    ) -> eyre::Result<AnyEntry>;
+    /// Every file of tag `id` of `registry`, lowest precedence first. Empty if
+    /// the tag does not exist.
+    fn tag_files(&self, registry: &str, id: &Ident<&str>) -> eyre::Result<Vec<Arc<TagFile>>>;
+    /// Drops everything cached, so entries and tags are read again the next
+    /// time they are requested. Entries handed out before stay valid.
+    fn reload(&self) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    /// The ids in tag `id` of `registry`, with nested tags expanded.
+    fn tag(&self, registry: &str, id: &Ident<&str>) -> eyre::Result<HashSet<Ident<String>>> {
+        tag::resolve(self, registry, id, &mut vec![])
+    }
+
+    fn biome(&self, id: &Ident<&str>) -> eyre::Result<Arc<Biome>> {
+        get(self, id)
+    }
+    fn configured_carver(&self, id: &Ident<&str>) -> eyre::Result<Arc<ConfiguredCarver>> {
+        get(self, id)
+    }
+    fn configured_feature(&self, id: &Ident<&str>) -> eyre::Result<Arc<ConfiguredFeature>> {
+        get(self, id)
+    }
+    fn density_function(&self, id: &Ident<&str>) -> eyre::Result<Arc<DensityFunctionTree>> {
+        get(self, id)
+    }
+    fn dimension_type(&self, id: &Ident<&str>) -> eyre::Result<Arc<DimensionType>> {
+        get(self, id)
+    }
+    fn multi_noise_biome_source_parameter_list(
+        &self,
+        id: &Ident<&str>,
+    ) -> eyre::Result<Arc<MultiNoiseBiomeSourceParameterList>> {
+        get(self, id)
+    }
+    fn noise(&self, id: &Ident<&str>) -> eyre::Result<Arc<NoiseParameters>> {
+        get(self, id)
+    }
     fn noise_generator_settings(
         &self,
         id: &Ident<&str>,
-    ) -> eyre::Result<Arc<NoiseGeneratorSettings>>;
+    ) -> eyre::Result<Arc<NoiseGeneratorSettings>> {
+        get(self, id)
+    }
+    fn placed_feature(&self, id: &Ident<&str>) -> eyre::Result<Arc<PlacedFeature>> {
+        get(self, id)
+    }
+    fn processor_list(&self, id: &Ident<&str>) -> eyre::Result<Arc<ProcessorList>> {
+        get(self, id)
+    }
+    fn structure(&self, id: &Ident<&str>) -> eyre::Result<Arc<Structure>> {
+        get(self, id)
+    }
+    fn structure_set(&self, id: &Ident<&str>) -> eyre::Result<Arc<StructureSet>> {
+        get(self, id)
+    }
+    fn template_pool(&self, id: &Ident<&str>) -> eyre::Result<Arc<TemplatePool>> {
+        get(self, id)
+    }
+    fn world_preset(&self, id: &Ident<&str>) -> eyre::Result<Arc<WorldPreset>> {
+        get(self, id)
+    }
+}
+
+/// Typed access to any registry, for registry trait objects as well.
+pub trait RegistryExt {
+    fn get<T: RegistryEntry>(&self, id: &Ident<&str>) -> eyre::Result<Arc<T>>;
+}
+
+impl<R: Registry + ?Sized> RegistryExt for R {
+    fn get<T: RegistryEntry>(&self, id: &Ident<&str>) -> eyre::Result<Arc<T>> {
+        get(self, id)
+    }
+}
+
+fn get<T: RegistryEntry, R: Registry + ?Sized>(
+    registry: &R,
+    id: &Ident<&str>,
+) -> eyre::Result<Arc<T>> {
+    registry
+        .entry(T::REGISTRY, id, parse::<T>)?
+        .downcast::<T>()
+        .map_err(|_| eyre!("{} {id} is not a {}", T::REGISTRY, type_name::<T>()))
+}
+
+fn parse<T: RegistryEntry>(
+    json: &[u8],
+) -> Result<AnyEntry, serde_path_to_error::Error<serde_json::Error>> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(json);
+    serde_path_to_error::deserialize::<_, T>(deserializer).map(|t| Arc::new(t) as AnyEntry)
 }
@@ -0,0 +1,250 @@
+use std::collections::HashSet;
+
+use eyre::eyre;
+use serde::Deserialize;
+use valence_core::ident::Ident;
+
+use crate::registry::Registry;
+
+/// A `tags/<registry>/*.json` file of a single pack, see [`Registry::tag`] for
+/// how the files of several packs are merged.
+#[derive(Deserialize, Clone, Debug)]
+pub struct TagFile {
+    #[serde(default)]
+    pub replace: bool,
+    pub values: Vec<TagValue>,
+}
+
+/// An entry id, or a nested tag written as `#namespace:path`. Values that are
+/// not `required` are skipped if they can't be found, missing required values
+/// fail the whole tag.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TagValue {
+    Id(String),
+    Optional {
+        id: String,
+        #[serde(default = "default_required")]
+        required: bool,
+    },
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl TagValue {
+    fn id(&self) -> &str {
+        match self {
+            TagValue::Id(id) | TagValue::Optional { id, .. } => id,
+        }
+    }
+
+    fn required(&self) -> bool {
+        match self {
+            TagValue::Id(_) => true,
+            TagValue::Optional { required, .. } => *required,
+        }
+    }
+}
+
+/// The path of the tags of `registry` below `data/<namespace>/`.
+pub fn tag_path(registry: &str) -> String {
+    format!("tags/{registry}")
+}
+
+/// Merges the files of tag `id` like vanilla: each file appends its values,
+/// unless it sets `replace`, then nested tags are expanded and ids are checked
+/// to exist in `kind`.
+pub(crate) fn resolve<R: Registry + ?Sized>(
+    registry: &R,
+    kind: &str,
+    id: &Ident<&str>,
+    chain: &mut Vec<String>,
+) -> eyre::Result<HashSet<Ident<String>>> {
+    if let Some(start) = chain.iter().position(|t| t == id.as_str()) {
+        return Err(eyre!(
+            "tag cycle in {kind}: #{} -> #{id}",
+            chain[start..].join(" -> #")
+        ));
+    }
+
+    let files = registry.tag_files(kind, id)?;
+    if files.is_empty() {
+        return Err(eyre!("tag #{id} of {kind} not found"));
+    }
+
+    let mut values = vec![];
+    for file in &files {
+        if file.replace {
+            values.clear();
+        }
+        values.extend(file.values.iter());
+    }
+
+    chain.push(id.to_string());
+    let mut ids = HashSet::new();
+    for value in values {
+        match value.id().strip_prefix('#') {
+            Some(tag) => {
+                let tag = Ident::new(tag)?;
+                if !value.required() && registry.tag_files(kind, &tag.as_str_ident())?.is_empty() {
+                    continue;
+                }
+
+                let nested = resolve(registry, kind, &tag.as_str_ident(), chain)
+                    .map_err(|e| e.wrap_err(format!("unable to expand tag #{id} of {kind}")))?;
+                ids.extend(nested);
+            }
+            None => {
+                let entry = Ident::new(value.id())?;
+                if !registry.contains(kind, &entry.as_str_ident()) {
+                    match value.required() {
+                        true => {
+                            return Err(eyre!("tag #{id} of {kind} references missing {entry}"))
+                        }
+                        false => continue,
+                    }
+                }
+
+                ids.insert(entry.to_string_ident());
+            }
+        }
+    }
+    chain.pop();
+
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use valence_core::ident;
+    use valence_core::ident::Ident;
+
+    use crate::biome::Biome;
+    use crate::registry::layered::LayeredRegistry;
+    use crate::registry::memory::MemoryRegistry;
+    use crate::registry::{Registry, BIOME};
+
+    fn tag(json: &str) -> super::TagFile {
+        serde_json::from_str(json).expect("should be a valid tag")
+    }
+
+    fn insert_biomes(registry: &MemoryRegistry, ids: &[&str]) {
+        for id in ids {
+            let biome = Biome {
+                temperature: 0.5,
+                temperature_modifier: Default::default(),
+                downfall: 0.5,
+                carvers: Default::default(),
+                features: vec![],
+            };
+            registry.insert(&Ident::new(*id).unwrap().as_str_ident(), biome);
+        }
+    }
+
+    #[test]
+    fn tag_merge_test() {
+        let vanilla = MemoryRegistry::new();
+        insert_biomes(
+            &vanilla,
+            &[
+                "minecraft:forest",
+                "minecraft:birch_forest",
+                "minecraft:plains",
+                "minecraft:desert",
+            ],
+        );
+        vanilla.insert_tag(
+            BIOME,
+            &ident!("minecraft:is_forest"),
+            tag(r#"{"values": ["minecraft:forest", "minecraft:birch_forest"]}"#),
+        );
+        vanilla.insert_tag(
+            BIOME,
+            &ident!("minecraft:is_overworld"),
+            tag(r##"{"values": ["#minecraft:is_forest", "minecraft:plains"]}"##),
+        );
+        vanilla.insert_tag(
+            BIOME,
+            &ident!("minecraft:has_structure/village"),
+            tag(r#"{"values": ["minecraft:plains", "minecraft:desert"]}"#),
+        );
+
+        let datapack = MemoryRegistry::new();
+        insert_biomes(&datapack, &["test:pine_forest"]);
+        datapack.insert_tag(
+            BIOME,
+            &ident!("minecraft:is_forest"),
+            tag(r##"{"values": [
+                  "test:pine_forest",
+                  {"id": "#test:missing", "required": false},
+                  {"id": "test:missing", "required": false}
+                ]}"##),
+        );
+        datapack.insert_tag(
+            BIOME,
+            &ident!("minecraft:has_structure/village"),
+            tag(r#"{"replace": true, "values": ["test:pine_forest"]}"#),
+        );
+        datapack.insert_tag(
+            BIOME,
+            &ident!("test:broken"),
+            tag(r#"{"values": ["minecraft:plains", "test:missing"]}"#),
+        );
+        datapack.insert_tag(
+            BIOME,
+            &ident!("test:uses_broken"),
+            tag(r##"{"values": ["#test:broken"]}"##),
+        );
+        datapack.insert_tag(
+            BIOME,
+            &ident!("test:a"),
+            tag(r##"{"values": ["#test:b"]}"##),
+        );
+        datapack.insert_tag(
+            BIOME,
+            &ident!("test:b"),
+            tag(r##"{"values": ["#test:a"]}"##),
+        );
+
+        let registry = LayeredRegistry::new()
+            .with_layer("vanilla", Box::new(vanilla))
+            .with_layer("datapack", Box::new(datapack));
+
+        let ids = |ids: &[&str]| -> HashSet<_> {
+            ids.iter()
+                .map(|i| Ident::new(*i).unwrap().to_string_ident())
+                .collect()
+        };
+        assert_eq!(
+            ids(&[
+                "minecraft:forest",
+                "minecraft:birch_forest",
+                "test:pine_forest",
+                "minecraft:plains"
+            ]),
+            registry
+                .tag(BIOME, &ident!("minecraft:is_overworld"))
+                .expect("should expand nested tags")
+        );
+        assert_eq!(
+            ids(&["test:pine_forest"]),
+            registry
+                .tag(BIOME, &ident!("minecraft:has_structure/village"))
+                .expect("should be replaced")
+        );
+
+        let cycle = registry.tag(BIOME, &ident!("test:a")).unwrap_err();
+        assert!(format!("{cycle:?}").contains("#test:a -> #test:b -> #test:a"));
+        assert!(registry.tag(BIOME, &ident!("test:missing")).is_err());
+
+        let broken = registry.tag(BIOME, &ident!("test:broken")).unwrap_err();
+        assert!(broken
+            .to_string()
+            .contains("references missing test:missing"));
+        assert!(registry.tag(BIOME, &ident!("test:uses_broken")).is_err());
+    }
+}
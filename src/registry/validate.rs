@@ -0,0 +1,426 @@
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use eyre::eyre;
+use valence_core::ident::Ident;
+
+use crate::biome::source::BiomeSource;
+use crate::dimension::Generator;
+use crate::feature::ConfiguredFeatureRef;
+use crate::noise::deserialize::NoiseSettings;
+use crate::random::random_state::RandomState;
+use crate::random::Kind;
+use crate::registry::holder_set::HolderSet;
+use crate::registry::layered::LayeredRegistry;
+use crate::registry::{
+    tag, DeserializeError, Registry, BIOME, CONFIGURED_CARVER, CONFIGURED_FEATURE,
+    DENSITY_FUNCTION, DIMENSION_TYPE, MULTI_NOISE_BIOME_SOURCE_PARAMETER_LIST, NOISE,
+    NOISE_GENERATOR_SETTINGS, PLACED_FEATURE, PROCESSOR_LIST, REGISTRIES, STRUCTURE, STRUCTURE_SET,
+    TEMPLATE_POOL, WORLD_PRESET,
+};
+use crate::structure::placement::PlacementKind;
+
+/// Every problem found by
+/// [`Datapack::validate`](crate::registry::datapack::Datapack::validate).
+///
+/// Density functions are compiled on their own, with the hardcoded cell
+/// sizes and height of the overworld's noise settings and seed 0, not with
+/// the noise settings using them.
+pub struct ValidationReport {
+    pub pack: String,
+    /// The number of entries and tags that were checked.
+    pub checked: usize,
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: checked {} entries, {} errors",
+            self.pack,
+            self.checked,
+            self.errors.len()
+        )?;
+        for error in &self.errors {
+            writeln!(f, "{error}")?;
+        }
+        Ok(())
+    }
+}
+
+pub struct ValidationError {
+    /// The registry of the entry, `tags/<registry>` for tags.
+    pub registry: String,
+    pub id: Ident<String>,
+    /// The file the error is in. This is the file of a referenced entry if
+    /// that one failed to deserialize.
+    pub file: PathBuf,
+    /// Where in `file` deserializing failed, see [`serde_path_to_error`].
+    pub path: Option<String>,
+    pub error: eyre::Report,
+}
+
+impl ValidationError {
+    fn new(registry: String, id: &Ident<String>, file: &str, error: eyre::Report) -> Self {
+        let deserialize = error
+            .chain()
+            .find_map(|e| e.downcast_ref::<DeserializeError>());
+        Self {
+            registry,
+            id: id.clone(),
+            file: deserialize.map_or_else(|| file.into(), |d| d.file.clone()),
+            path: deserialize.map(|d| d.path.clone()),
+            error,
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(path) = &self.path {
+            write!(f, "::{path}")?;
+        }
+        write!(f, " ({} {}): {:#}", self.registry, self.id, self.error)
+    }
+}
+
+/// Checks every entry and tag in `files` of `pack`, stacked on `base`.
+pub(crate) fn validate(
+    name: String,
+    files: &[String],
+    pack: Box<dyn Registry>,
+    base: Option<Box<dyn Registry>>,
+) -> ValidationReport {
+    let mut layered = LayeredRegistry::new();
+    if let Some(base) = base {
+        layered.push_layer("base", base);
+    }
+    layered.push_layer(name.clone(), pack);
+    let registry: Arc<dyn Registry> = Arc::new(layered);
+
+    // see `ValidationReport`, density functions are compiled with overworld
+    // noise settings
+    let random_state = RandomState::from_parts(
+        NoiseSettings {
+            min_y: -64,
+            height: 384,
+            xz_size: 1,
+            y_size: 2,
+        },
+        &Kind::Xoroshiro,
+        registry.clone(),
+        0,
+    );
+
+    let mut report = ValidationReport {
+        pack: name,
+        checked: 0,
+        errors: vec![],
+    };
+    for file in files {
+        let Some((kind, id, is_tag)) = parse_path(file) else {
+            continue;
+        };
+
+        let mut references = References {
+            registry: registry.as_ref(),
+            errors: vec![],
+        };
+        let result = match is_tag {
+            true => check_tag(registry.as_ref(), kind, &id),
+            false => check_entry(&mut references, &random_state, kind, &id),
+        };
+
+        let registry = match is_tag {
+            true => tag::tag_path(kind),
+            false => kind.to_string(),
+        };
+        report.checked += 1;
+        report.errors.extend(
+            result
+                .err()
+                .into_iter()
+                .chain(references.errors)
+                .map(|e| ValidationError::new(registry.clone(), &id, file, e)),
+        );
+    }
+
+    report
+}
+
+/// The registry, id and whether it is a tag of a pack file, `None` if it is
+/// not a worldgen entry.
+fn parse_path(file: &str) -> Option<(&'static str, Ident<String>, bool)> {
+    let rest = file.strip_prefix("data/")?.strip_suffix(".json")?;
+    let (namespace, rest) = rest.split_once('/')?;
+    let (rest, is_tag) = match rest.strip_prefix("tags/") {
+        Some(rest) => (rest, true),
+        None => (rest, false),
+    };
+
+    let kind = REGISTRIES
+        .iter()
+        .find(|r| rest.strip_prefix(**r).is_some_and(|p| p.starts_with('/')))?;
+    let id = Ident::new(format!("{namespace}:{}", &rest[kind.len() + 1..])).ok()?;
+
+    Some((kind, id.to_string_ident(), is_tag))
+}
+
+/// Collects missing references of an entry, so all of them are reported.
+struct References<'a> {
+    registry: &'a dyn Registry,
+    errors: Vec<eyre::Report>,
+}
+
+impl References<'_> {
+    fn check(&mut self, kind: &str, id: &Ident<String>) {
+        if !self.registry.contains(kind, &id.as_str_ident()) {
+            self.errors.push(eyre!("references missing {kind} {id}"));
+        }
+    }
+
+    /// Tags are only resolved here, their contents are checked with the tag.
+    fn check_holders(&mut self, kind: &str, holders: &HolderSet) {
+        match holders {
+            HolderSet::Direct(ids) => ids.iter().for_each(|id| self.check(kind, id)),
+            HolderSet::Tag(_) => {
+                if let Err(e) = holders.resolve(self.registry, kind) {
+                    self.errors.push(e);
+                }
+            }
+        }
+    }
+}
+
+/// Resolving a tag fails if a required entry is missing.
+fn check_tag(registry: &dyn Registry, kind: &str, id: &Ident<String>) -> eyre::Result<()> {
+    registry.tag(kind, &id.as_str_ident())?;
+    Ok(())
+}
+
+fn check_entry(
+    references: &mut References,
+    random_state: &RandomState,
+    kind: &str,
+    id: &Ident<String>,
+) -> eyre::Result<()> {
+    let registry = references.registry;
+    let id = &id.as_str_ident();
+    match kind {
+        BIOME => {
+            let biome = registry.biome(id)?;
+            for carvers in biome.carvers.sets() {
+                references.check_holders(CONFIGURED_CARVER, carvers);
+            }
+            for features in &biome.features {
+                references.check_holders(PLACED_FEATURE, features);
+            }
+        }
+        CONFIGURED_CARVER => {
+            registry.configured_carver(id)?;
+        }
+        CONFIGURED_FEATURE => {
+            registry.configured_feature(id)?;
+        }
+        DENSITY_FUNCTION => {
+            registry.density_function(id)?.compile(random_state)?;
+        }
+        DIMENSION_TYPE => {
+            registry.dimension_type(id)?;
+        }
+        MULTI_NOISE_BIOME_SOURCE_PARAMETER_LIST => {
+            registry.multi_noise_biome_source_parameter_list(id)?;
+        }
+        NOISE => {
+            registry.noise(id)?;
+        }
+        NOISE_GENERATOR_SETTINGS => {
+            let settings = registry.noise_generator_settings(id)?;
+            let random_state =
+                RandomState::new(&settings, random_state.registry.clone(), random_state.seed);
+            settings.noise_router.compile(&random_state)?;
+        }
+        PLACED_FEATURE => {
+            if let ConfiguredFeatureRef::Reference(feature) = &registry.placed_feature(id)?.feature
+            {
+                references.check(CONFIGURED_FEATURE, feature);
+            }
+        }
+        PROCESSOR_LIST => {
+            registry.processor_list(id)?;
+        }
+        STRUCTURE => {
+            references.check_holders(BIOME, &registry.structure(id)?.biomes);
+        }
+        STRUCTURE_SET => {
+            let set = registry.structure_set(id)?;
+            for entry in &set.structures {
+                references.check(STRUCTURE, &entry.structure);
+            }
+            if let PlacementKind::ConcentricRings {
+                preferred_biomes, ..
+            } = &set.placement.kind
+            {
+                references.check_holders(BIOME, preferred_biomes);
+            }
+        }
+        TEMPLATE_POOL => {
+            references.check(TEMPLATE_POOL, &registry.template_pool(id)?.fallback);
+        }
+        WORLD_PRESET => {
+            for stem in registry.world_preset(id)?.dimensions.values() {
+                references.check(DIMENSION_TYPE, &stem.dimension_type);
+                let Generator::Noise {
+                    settings,
+                    biome_source,
+                } = &stem.generator
+                else {
+                    continue;
+                };
+
+                references.check(NOISE_GENERATOR_SETTINGS, settings);
+                match biome_source {
+                    BiomeSource::Fixed { biome } => references.check(BIOME, biome),
+                    BiomeSource::MultiNoise { biomes, preset } => {
+                        for entry in biomes {
+                            references.check(BIOME, &entry.biome);
+                        }
+                        if let Some(preset) = preset {
+                            references.check(MULTI_NOISE_BIOME_SOURCE_PARAMETER_LIST, preset);
+                        }
+                    }
+                    BiomeSource::TheEnd {} => {}
+                }
+            }
+        }
+        _ => return Err(eyre!("{kind} can not be validated")),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::registry::datapack::Datapack;
+    use crate::registry::{BIOME, DENSITY_FUNCTION, NOISE, STRUCTURE};
+    use crate::test::TempDir;
+
+    #[test]
+    fn validation_report_test() {
+        let root = TempDir::new("validate");
+        root.write(&[
+            (
+                "pack.mcmeta",
+                r#"{"pack": {"pack_format": 15, "description": "test"}}"#,
+            ),
+            (
+                "data/test/worldgen/noise/a.json",
+                r#"{"firstOctave": -3, "amplitudes": [1.0]}"#,
+            ),
+            (
+                "data/test/worldgen/noise/broken.json",
+                r#"{"firstOctave": -3, "amplitudes": [1.0, "x"]}"#,
+            ),
+            (
+                "data/test/worldgen/density_function/valid.json",
+                r#"{"type": "minecraft:noise", "noise": "test:a", "xz_scale": 1, "y_scale": 1}"#,
+            ),
+            (
+                "data/test/worldgen/density_function/missing_noise.json",
+                r#"{"type": "minecraft:noise", "noise": "test:b", "xz_scale": 1, "y_scale": 1}"#,
+            ),
+            (
+                "data/test/worldgen/density_function/broken.json",
+                r#"{"type": "minecraft:add", "argument1": 1, "argument2": {"type": "minecraft:abs"}}"#,
+            ),
+            (
+                "data/test/worldgen/density_function/slide.json",
+                r#"{"type": "minecraft:slide", "argument": 1}"#,
+            ),
+            (
+                "data/test/worldgen/density_function/uses_broken.json",
+                r#"{"type": "minecraft:abs", "argument": "test:broken"}"#,
+            ),
+            (
+                "data/test/worldgen/structure/tower.json",
+                r#"{"type": "minecraft:jigsaw", "biomes": ["test:plains", "test:hills"]}"#,
+            ),
+            (
+                "data/test/tags/worldgen/biome/is_hilly.json",
+                r#"{"values": ["test:hills"]}"#,
+            ),
+            ("data/test/worldgen/biome/hills.json", "{}"),
+            ("data/test/functions/ignored.mcfunction", "say hi"),
+        ]);
+
+        let report = Datapack::open(root.path())
+            .expect("should be a valid datapack")
+            .validate(None)
+            .expect("should list the pack");
+        assert_eq!(10, report.checked);
+        assert!(!report.is_ok());
+
+        let errors = report
+            .errors
+            .iter()
+            .map(|e| {
+                (
+                    e.registry.as_str(),
+                    e.id.as_str(),
+                    e.file.to_str().unwrap(),
+                    e.path.as_deref(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let broken = "data/test/worldgen/density_function/broken.json";
+        assert_eq!(
+            vec![
+                (
+                    BIOME,
+                    "test:hills",
+                    "data/test/worldgen/biome/hills.json",
+                    Some(".")
+                ),
+                (DENSITY_FUNCTION, "test:broken", broken, Some(".")),
+                (
+                    DENSITY_FUNCTION,
+                    "test:missing_noise",
+                    "data/test/worldgen/density_function/missing_noise.json",
+                    None
+                ),
+                (
+                    DENSITY_FUNCTION,
+                    "test:slide",
+                    "data/test/worldgen/density_function/slide.json",
+                    None
+                ),
+                (DENSITY_FUNCTION, "test:uses_broken", broken, Some(".")),
+                (
+                    NOISE,
+                    "test:broken",
+                    "data/test/worldgen/noise/broken.json",
+                    Some("amplitudes[1]")
+                ),
+                (
+                    STRUCTURE,
+                    "test:tower",
+                    "data/test/worldgen/structure/tower.json",
+                    None
+                ),
+            ],
+            errors
+        );
+        assert!(report
+            .to_string()
+            .contains("references missing worldgen/biome test:plains"));
+    }
+}
@@ -2,9 +2,11 @@ use std::marker::PhantomData;
 use std::sync::Arc;
 
 use eyre::eyre;
-use serde::{Deserialize, Deserializer};
+use serde::ser::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use valence_core::block_pos::BlockPos;
 
+use crate::density_function::compile::CompileContext;
 use crate::density_function::deserialize::DensityFunctionTree;
 use crate::density_function::DensityFunction;
 use crate::random::random_state::RandomState;
@@ -51,6 +53,33 @@ impl<'de> Deserialize<'de> for CubicSpline<Blueprint> {
     }
 }
 
+impl Serialize for CubicSpline<Blueprint> {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        #[serde(untagged)]
+        enum Raw<'a> {
+            Constant(f32),
+            MultipointBlueprint {
+                coordinate: &'a DensityFunctionTree,
+                points: &'a [CubicSplinePoint<Blueprint>],
+            },
+        }
+        match self {
+            CubicSpline::Constant(arg) => Raw::Constant(*arg),
+            CubicSpline::MultipointBlueprint { coordinate, points } => {
+                Raw::MultipointBlueprint { coordinate, points }
+            }
+            CubicSpline::Multipoint { .. } => {
+                return Err(S::Error::custom("a compiled spline can't be serialized"))
+            }
+        }
+        .serialize(s)
+    }
+}
+
 #[derive(Clone)]
 pub struct CubicSplinePoint<State = Blueprint> {
     marker: PhantomData<State>,
@@ -79,8 +108,72 @@ impl<'de> Deserialize<'de> for CubicSplinePoint<Blueprint> {
     }
 }
 
+impl CubicSplinePoint<Blueprint> {
+    pub fn new(location: f32, value: CubicSpline<Blueprint>, derivative: f32) -> Self {
+        Self {
+            marker: PhantomData,
+            location,
+            derivative,
+            value,
+        }
+    }
+}
+
+impl Serialize for CubicSplinePoint<Blueprint> {
+    fn serialize<S>(&self, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Raw<'a> {
+            location: f32,
+            value: &'a CubicSpline<Blueprint>,
+            derivative: f32,
+        }
+        Raw {
+            location: self.location,
+            value: &self.value,
+            derivative: self.derivative,
+        }
+        .serialize(s)
+    }
+}
+
 impl CubicSpline<Blueprint> {
+    /// This spline with every coordinate replaced by `f` of it.
+    pub(crate) fn try_map_coordinates(
+        &self,
+        f: &mut impl FnMut(&Arc<DensityFunctionTree>) -> eyre::Result<Arc<DensityFunctionTree>>,
+    ) -> eyre::Result<Self> {
+        Ok(match self {
+            CubicSpline::MultipointBlueprint { coordinate, points } => {
+                CubicSpline::MultipointBlueprint {
+                    coordinate: f(coordinate)?,
+                    points: points
+                        .iter()
+                        .map(|point| {
+                            Ok(CubicSplinePoint {
+                                marker: PhantomData,
+                                location: point.location,
+                                derivative: point.derivative,
+                                value: point.value.try_map_coordinates(f)?,
+                            })
+                        })
+                        .collect::<eyre::Result<_>>()?,
+                }
+            }
+            spline => spline.clone(),
+        })
+    }
+
     pub fn compile(&self, random_state: &RandomState) -> eyre::Result<CubicSpline<Built>> {
+        self.compile_with(&mut CompileContext::new(random_state))
+    }
+
+    pub(crate) fn compile_with(
+        &self,
+        ctx: &mut CompileContext,
+    ) -> eyre::Result<CubicSpline<Built>> {
         Ok(match self {
             CubicSpline::Constant(arg) => CubicSpline::Constant(*arg),
             CubicSpline::Multipoint { coordinate, points } => CubicSpline::<Built>::Multipoint {
@@ -93,8 +186,8 @@ impl CubicSpline<Blueprint> {
                 }
 
                 CubicSpline::<Built>::Multipoint {
-                    coordinate: Arc::from(coordinate.compile(random_state)?),
-                    points: points.iter().map(|p| p.compile(random_state)).fold(
+                    coordinate: Arc::from(coordinate.compile_with(ctx)?),
+                    points: points.iter().map(|p| p.compile_with(ctx)).fold(
                         Ok(Vec::with_capacity(points.len())),
                         |acc, res| {
                             let mut points = match acc {
@@ -153,35 +246,107 @@ impl CubicSpline<Built> {
     }
 
     pub fn min(&self) -> f32 {
-        match self {
-            CubicSpline::Constant(c) => *c,
-            CubicSpline::Multipoint { points, .. } => points
-                .iter()
-                .fold(f32::INFINITY, |acc, b| f32::min(acc, b.value.min())),
+        self.bounds().0
+    }
 
-            CubicSpline::MultipointBlueprint { .. } => unreachable!(),
-        }
+    /// The coordinate and the nested splines, see [`DensityFunction::describe`].
+    pub(crate) fn arguments(&self) -> Vec<&dyn DensityFunction> {
+        let CubicSpline::Multipoint { coordinate, points } = self else {
+            return vec![];
+        };
+
+        let nested = points
+            .iter()
+            .filter(|p| matches!(p.value, CubicSpline::Multipoint { .. }))
+            .map(|p| &p.value as &dyn DensityFunction);
+        std::iter::once(coordinate.as_ref()).chain(nested).collect()
     }
 
     pub fn max(&self) -> f32 {
-        match self {
-            CubicSpline::Constant(c) => *c,
-            CubicSpline::Multipoint { points, .. } => points
-                .iter()
-                .fold(f32::NEG_INFINITY, |acc, b| f32::max(acc, b.value.max())),
+        self.bounds().1
+    }
 
+    /// Bounds like vanilla's `CubicSpline.Multipoint.create`, including the
+    /// linear extension past the outer points and the overshoot of cubic
+    /// segments.
+    fn bounds(&self) -> (f32, f32) {
+        let (coordinate, points) = match self {
+            CubicSpline::Constant(c) => return (*c, *c),
+            CubicSpline::Multipoint { coordinate, points } => (coordinate, points),
             CubicSpline::MultipointBlueprint { .. } => unreachable!(),
+        };
+
+        let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+        let first = points.first().expect("should contain at least one part");
+        let last = points.last().expect("should contain at least one part");
+        let extended = [
+            (first, coordinate.min() as f32),
+            (last, coordinate.max() as f32),
+        ];
+        for (i, (p, x)) in extended.into_iter().enumerate() {
+            let outside = match i {
+                0 => x < p.location,
+                _ => x > p.location,
+            };
+            if outside {
+                let (value_min, value_max) = p.value.bounds();
+                for v in [p.extend(x, value_min), p.extend(x, value_max)] {
+                    min = min.min(v);
+                    max = max.max(v);
+                }
+            }
+        }
+
+        for p in points {
+            let (value_min, value_max) = p.value.bounds();
+            min = min.min(value_min);
+            max = max.max(value_max);
+        }
+
+        for segment in points.windows(2) {
+            let (p0, p1) = (&segment[0], &segment[1]);
+            if p0.derivative == 0.0 && p1.derivative == 0.0 {
+                continue;
+            }
+
+            let span = p1.location - p0.location;
+            let (min0, max0) = p0.value.bounds();
+            let (min1, max1) = p1.value.bounds();
+            let (d0, d1) = (p0.derivative * span, p1.derivative * span);
+            let low = f32::min(d0 - max1 + min0, -d1 + min1 - max0);
+            let high = f32::max(d0 - min1 + max0, -d1 + max1 - min0);
+            min = min.min(f32::min(min0, min1) + 0.25 * low);
+            max = max.max(f32::max(max0, max1) + 0.25 * high);
+        }
+
+        (min, max)
+    }
+}
+
+impl CubicSplinePoint<Built> {
+    /// The value at `x` outside the spline, extended linearly from this point.
+    fn extend(&self, x: f32, value: f32) -> f32 {
+        match self.derivative {
+            0.0 => value,
+            d => value + d * (x - self.location),
         }
     }
 }
 
 impl CubicSplinePoint<Blueprint> {
     pub fn compile(&self, random_state: &RandomState) -> eyre::Result<CubicSplinePoint<Built>> {
+        self.compile_with(&mut CompileContext::new(random_state))
+    }
+
+    pub(crate) fn compile_with(
+        &self,
+        ctx: &mut CompileContext,
+    ) -> eyre::Result<CubicSplinePoint<Built>> {
         Ok(CubicSplinePoint::<Built> {
             marker: Default::default(),
             derivative: self.derivative,
             location: self.location,
-            value: self.value.compile(random_state)?,
+            value: self.value.compile_with(ctx)?,
         })
     }
 }
@@ -302,4 +467,30 @@ mod test {
             assert_eq!(s, spline.compute(BlockPos::new(0, r.next_i32_between_inclusive((-96, 96)), 0)) as f64)
         }
     }
+
+    #[test]
+    fn spline_bounds_test() {
+        let f =
+            crate::density_function::y_clamped_gradient::YClampedGradient::new(-64, 64, -2.0, 2.0)
+                .unwrap();
+        let point = |location: f32, derivative: f32, value: f32| CubicSplinePoint::<Built> {
+            marker: Default::default(),
+            location,
+            derivative,
+            value: CubicSpline::<Built>::Constant(value),
+        };
+
+        // overshoots between the points and is extended past them
+        let spline = CubicSpline::<Built>::Multipoint {
+            coordinate: f.into(),
+            points: vec![point(-1.0, 4.0, 0.0), point(1.0, 4.0, 1.0)],
+        };
+
+        let (min, max) = (spline.min(), spline.max());
+        assert!(min < 0.0 && max > 1.0);
+        for y in -64..=64 {
+            let v = spline.compute(BlockPos::new(0, y, 0));
+            assert!((min..=max).contains(&v), "{v} at {y} not in {min}..{max}");
+        }
+    }
 }
@@ -0,0 +1,103 @@
+use serde::Deserialize;
+use valence_core::block_pos::BlockPos;
+use valence_core::ident::Ident;
+
+use crate::registry::holder_set::HolderSet;
+
+pub mod placement;
+pub mod pool;
+
+/// A `worldgen/structure`, only the parts needed without generating its
+/// pieces.
+#[derive(Deserialize)]
+pub struct Structure {
+    #[serde(rename = "type")]
+    pub kind: Ident<String>,
+    pub biomes: HolderSet,
+    #[serde(default)]
+    pub terrain_adaptation: TerrainAdjustment,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BoundingBox {
+    pub min: BlockPos,
+    pub max: BlockPos,
+}
+
+impl BoundingBox {
+    pub fn new(min: BlockPos, max: BlockPos) -> Self {
+        Self {
+            min: BlockPos::new(min.x.min(max.x), min.y.min(max.y), min.z.min(max.z)),
+            max: BlockPos::new(min.x.max(max.x), min.y.max(max.y), min.z.max(max.z)),
+        }
+    }
+
+    pub fn intersects_xz(&self, min_x: i32, min_z: i32, max_x: i32, max_z: i32) -> bool {
+        self.max.x >= min_x && self.min.x <= max_x && self.max.z >= min_z && self.min.z <= max_z
+    }
+
+    pub fn inflated_by(&self, amount: i32) -> Self {
+        Self {
+            min: BlockPos::new(
+                self.min.x - amount,
+                self.min.y - amount,
+                self.min.z - amount,
+            ),
+            max: BlockPos::new(
+                self.max.x + amount,
+                self.max.y + amount,
+                self.max.z + amount,
+            ),
+        }
+    }
+
+    pub fn is_inside(&self, pos: BlockPos) -> bool {
+        pos.x >= self.min.x
+            && pos.x <= self.max.x
+            && pos.y >= self.min.y
+            && pos.y <= self.max.y
+            && pos.z >= self.min.z
+            && pos.z <= self.max.z
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum TerrainAdjustment {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "bury")]
+    Bury,
+    #[serde(rename = "beard_thin")]
+    BeardThin,
+    #[serde(rename = "beard_box")]
+    BeardBox,
+    #[serde(rename = "encapsulate")]
+    Encapsulate,
+}
+
+/// A rigid structure piece terrain has to adapt to.
+#[derive(Copy, Clone, Debug)]
+pub struct RigidPiece {
+    pub bounding_box: BoundingBox,
+    pub terrain_adjustment: TerrainAdjustment,
+    pub ground_level_delta: i32,
+}
+
+/// A jigsaw connection leading from a rigid piece into a terrain matching one.
+#[derive(Copy, Clone, Debug)]
+pub struct JigsawJunction {
+    pub source_x: i32,
+    pub source_ground_y: i32,
+    pub source_z: i32,
+    pub delta_y: i32,
+    pub dest_projection: Projection,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Projection {
+    #[serde(rename = "rigid")]
+    Rigid,
+    #[serde(rename = "terrain_matching")]
+    TerrainMatching,
+}
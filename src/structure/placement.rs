@@ -0,0 +1,235 @@
+use eyre::eyre;
+use serde::Deserialize;
+use valence_core::block_pos::BlockPos;
+use valence_core::ident::Ident;
+
+use crate::random::legacy::LegacyRandom;
+use crate::random::RandomSource;
+use crate::registry::holder_set::HolderSet;
+
+/// A `worldgen/structure_set`, structures sharing one placement.
+#[derive(Deserialize)]
+pub struct StructureSet {
+    pub structures: Vec<StructureSelectionEntry>,
+    pub placement: StructurePlacement,
+}
+
+#[derive(Deserialize)]
+pub struct StructureSelectionEntry {
+    pub structure: Ident<String>,
+    pub weight: u32,
+}
+
+#[derive(Deserialize)]
+pub struct StructurePlacement {
+    #[serde(flatten)]
+    pub kind: PlacementKind,
+    pub salt: i32,
+    #[serde(default = "default_frequency")]
+    pub frequency: f32,
+    #[serde(default)]
+    pub frequency_reduction_method: FrequencyReductionMethod,
+    #[serde(default)]
+    pub locate_offset: [i32; 3],
+    pub exclusion_zone: Option<ExclusionZone>,
+}
+
+fn default_frequency() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum PlacementKind {
+    #[serde(rename = "minecraft:random_spread")]
+    RandomSpread {
+        spacing: i32,
+        separation: i32,
+        #[serde(default)]
+        spread_type: SpreadType,
+    },
+    #[serde(rename = "minecraft:concentric_rings")]
+    ConcentricRings {
+        distance: i32,
+        spread: i32,
+        count: i32,
+        preferred_biomes: HolderSet,
+    },
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum SpreadType {
+    #[default]
+    #[serde(rename = "linear")]
+    Linear,
+    #[serde(rename = "triangular")]
+    Triangular,
+}
+
+impl SpreadType {
+    fn evaluate(&self, r: &mut dyn RandomSource, limit: i32) -> i32 {
+        match self {
+            SpreadType::Linear => r.next_i32_bound(limit),
+            SpreadType::Triangular => (r.next_i32_bound(limit) + r.next_i32_bound(limit)) / 2,
+        }
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum FrequencyReductionMethod {
+    #[default]
+    #[serde(rename = "default")]
+    Default,
+    #[serde(rename = "legacy_type_1")]
+    LegacyType1,
+    #[serde(rename = "legacy_type_2")]
+    LegacyType2,
+    #[serde(rename = "legacy_type_3")]
+    LegacyType3,
+}
+
+impl FrequencyReductionMethod {
+    fn should_generate(&self, seed: i64, salt: i32, x: i32, z: i32, frequency: f32) -> bool {
+        match self {
+            // vanilla passes the salt as x and the chunk coordinates as z and salt
+            FrequencyReductionMethod::Default => {
+                large_feature_with_salt(seed, salt, x, z).next_f32() < frequency
+            }
+            FrequencyReductionMethod::LegacyType1 => {
+                let mut r = LegacyRandom::new(((x >> 4) ^ ((z >> 4) << 4)) as i64 ^ seed);
+                r.next_i32();
+                r.next_i32_bound((1.0 / frequency) as i32) == 0
+            }
+            FrequencyReductionMethod::LegacyType2 => {
+                large_feature_with_salt(seed, x, z, 10387320).next_f32() < frequency
+            }
+            FrequencyReductionMethod::LegacyType3 => {
+                large_feature(seed, x, z).next_f64() < frequency as f64
+            }
+        }
+    }
+}
+
+/// No structure of the placement starts within `chunk_count` chunks of a
+/// structure chunk of `other_set`.
+#[derive(Deserialize)]
+pub struct ExclusionZone {
+    pub other_set: Ident<String>,
+    pub chunk_count: i32,
+}
+
+impl StructurePlacement {
+    /// The chunk the structure of the spread region containing chunk `x`, `z`
+    /// would start in, before frequency reduction.
+    pub fn potential_structure_chunk(&self, seed: i64, x: i32, z: i32) -> eyre::Result<(i32, i32)> {
+        let PlacementKind::RandomSpread {
+            spacing,
+            separation,
+            spread_type,
+        } = &self.kind
+        else {
+            return Err(eyre!("only random spread placements use a grid"));
+        };
+
+        if spacing <= separation {
+            return Err(eyre!(
+                "spacing has to be larger than separation, {spacing} <= {separation}"
+            ));
+        }
+
+        let (region_x, region_z) = (x.div_euclid(*spacing), z.div_euclid(*spacing));
+        let mut r = large_feature_with_salt(seed, region_x, region_z, self.salt);
+        let limit = spacing - separation;
+        let offset_x = spread_type.evaluate(r.as_mut(), limit);
+        let offset_z = spread_type.evaluate(r.as_mut(), limit);
+
+        Ok((region_x * spacing + offset_x, region_z * spacing + offset_z))
+    }
+
+    /// Whether chunk `x`, `z` is the chunk of its spread region, before
+    /// frequency reduction and exclusion zones.
+    pub fn is_placement_chunk(&self, seed: i64, x: i32, z: i32) -> eyre::Result<bool> {
+        Ok(self.potential_structure_chunk(seed, x, z)? == (x, z))
+    }
+
+    /// Whether a placement chunk `x`, `z` survives frequency reduction.
+    pub fn should_generate(&self, seed: i64, x: i32, z: i32) -> bool {
+        self.frequency >= 1.0
+            || self.frequency_reduction_method.should_generate(
+                seed,
+                self.salt,
+                x,
+                z,
+                self.frequency,
+            )
+    }
+
+    /// The position `/locate` reports for a structure starting in chunk `x`,
+    /// `z`.
+    pub fn locate_pos(&self, x: i32, z: i32) -> BlockPos {
+        let [dx, dy, dz] = self.locate_offset;
+        BlockPos::new((x << 4) + dx, dy, (z << 4) + dz)
+    }
+}
+
+/// Vanilla's `WorldgenRandom.setLargeFeatureWithSalt`.
+fn large_feature_with_salt(seed: i64, x: i32, z: i32, salt: i32) -> Box<dyn RandomSource> {
+    LegacyRandom::new(
+        (x as i64)
+            .wrapping_mul(341873128712)
+            .wrapping_add((z as i64).wrapping_mul(132897987541))
+            .wrapping_add(seed)
+            .wrapping_add(salt as i64),
+    )
+}
+
+/// Vanilla's `WorldgenRandom.setLargeFeatureSeed`.
+fn large_feature(seed: i64, x: i32, z: i32) -> Box<dyn RandomSource> {
+    let mut r = LegacyRandom::new(seed);
+    let a = r.next_i64();
+    let b = r.next_i64();
+    LegacyRandom::new((x as i64).wrapping_mul(a) ^ (z as i64).wrapping_mul(b) ^ seed)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::structure::placement::StructureSet;
+
+    #[test]
+    fn random_spread_grid_test() {
+        let set: StructureSet = serde_json::from_str(
+            r#"{
+              "placement": {
+                "type": "minecraft:random_spread",
+                "salt": 10387312,
+                "separation": 8,
+                "spacing": 34
+              },
+              "structures": [
+                {
+                  "structure": "minecraft:village_plains",
+                  "weight": 1
+                }
+              ]
+            }"#,
+        )
+        .expect("should be a valid structure set");
+
+        let seed = 6646468147532173577;
+        for (region_x, region_z) in [(0, 0), (-1, 3), (17, -42)] {
+            let (x, z) = set
+                .placement
+                .potential_structure_chunk(seed, region_x * 34, region_z * 34)
+                .expect("villages are placed on a grid");
+            assert!((0..34 - 8).contains(&(x - region_x * 34)));
+            assert!((0..34 - 8).contains(&(z - region_z * 34)));
+
+            let starts = (region_x * 34..region_x * 34 + 34)
+                .flat_map(|x| (region_z * 34..region_z * 34 + 34).map(move |z| (x, z)))
+                .filter(|&(x, z)| set.placement.is_placement_chunk(seed, x, z).unwrap())
+                .filter(|&(x, z)| set.placement.should_generate(seed, x, z))
+                .collect::<Vec<_>>();
+            assert_eq!(vec![(x, z)], starts);
+        }
+    }
+}
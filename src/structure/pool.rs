@@ -0,0 +1,22 @@
+use serde::Deserialize;
+use valence_core::ident::Ident;
+
+/// A `worldgen/template_pool` jigsaw structures pick their pieces from.
+#[derive(Deserialize)]
+pub struct TemplatePool {
+    pub fallback: Ident<String>,
+    pub elements: Vec<WeightedPoolElement>,
+}
+
+#[derive(Deserialize)]
+pub struct WeightedPoolElement {
+    pub weight: u32,
+    /// Pieces are not placed yet, so elements are kept as is.
+    pub element: serde_json::Value,
+}
+
+/// A `worldgen/processor_list` applied to structure templates.
+#[derive(Deserialize)]
+pub struct ProcessorList {
+    pub processors: Vec<serde_json::Value>,
+}
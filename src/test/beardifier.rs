@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+use valence_core::ident;
+
+use crate::biome::source::BiomeSource;
+use crate::chunk_generator::ChunkGenerator;
+use crate::density_function::deserialize::DensityFunctionTree;
+use crate::noise::deserialize::NoiseGeneratorSettings;
+use crate::random::random_state::RandomState;
+use crate::registry::memory::MemoryRegistry;
+use crate::structure::{BoundingBox, RigidPiece, TerrainAdjustment};
+use crate::test::noise_settings;
+
+/// Solid up to y = 64, with the beardifier added on top.
+const FINAL_DENSITY: &str = r#"{
+  "type": "minecraft:add",
+  "argument1": {"type": "minecraft:y_clamped_gradient", "from_y": 0, "to_y": 128, "from_value": 0.5, "to_value": -0.5},
+  "argument2": {"type": "minecraft:beardifier"}
+}"#;
+
+#[test]
+fn chunk_router_adapts_terrain() {
+    // air instead of water above the terrain
+    let settings = Arc::new(NoiseGeneratorSettings {
+        sea_level: -64,
+        ..noise_settings()
+    });
+    let final_density: DensityFunctionTree =
+        serde_json::from_str(FINAL_DENSITY).expect("should be a valid density function");
+    let registry = Arc::new(
+        MemoryRegistry::new().with_density_function(&ident!("test:final_density"), final_density),
+    );
+
+    let random_state = Arc::new(RandomState::new(&settings, registry, 0));
+    let generator = ChunkGenerator::new(
+        settings,
+        BiomeSource::Fixed {
+            biome: ident!("minecraft:plains").to_string_ident(),
+        },
+        random_state,
+    )
+    .expect("generator should build");
+
+    // a piece buried in the air above the terrain
+    let piece = RigidPiece {
+        bounding_box: BoundingBox::new(BlockPos::new(0, 80, 0), BlockPos::new(8, 88, 8)),
+        terrain_adjustment: TerrainAdjustment::Bury,
+        ground_level_delta: 1,
+    };
+    let router = generator
+        .chunk_router(0, 0, [piece], [])
+        .expect("chunk router should compile");
+
+    let pos = BlockPos::new(4, 81, 4);
+    assert!(router.final_density.compute(pos) > 0.0, "buries the piece");
+    assert!(generator.router().final_density.compute(pos) < 0.0);
+
+    let y = (pos.y - generator.min_y()) as usize;
+    let adapted = generator.base_column_with(&router, 4, 4);
+    assert_eq!(generator.settings().default_block, adapted[y]);
+    assert!(adapted[y + 10].is_air());
+    assert!(generator.base_column(4, 4)[y].is_air());
+
+    // far from the piece
+    let router = generator
+        .chunk_router(8, 8, [piece], [])
+        .expect("chunk router should compile");
+    assert!(router.final_density.compute(BlockPos::new(132, 81, 132)) < 0.0);
+}
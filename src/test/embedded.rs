@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use valence_core::ident;
+
+use crate::random::random_state::RandomState;
+use crate::registry::embedded::EmbeddedRegistry;
+use crate::registry::{Registry, NOISE};
+
+#[test]
+fn embedded_overworld() {
+    let registry = Arc::new(EmbeddedRegistry::new());
+
+    assert!(registry.contains(NOISE, &ident!("minecraft:continentalness")));
+    assert!(!registry.contains(NOISE, &ident!("minecraft:does_not_exist")));
+
+    let settings = registry
+        .noise_generator_settings(&ident!("minecraft:overworld"))
+        .expect("should load overworld noise generator settings");
+
+    let random_state = RandomState::new(&settings, registry.clone(), 6646468147532173577);
+    settings
+        .noise_router
+        .compile(&random_state)
+        .expect("noise router should compile");
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use valence_core::ident;
+
+use crate::biome::source::BiomeSource;
+use crate::chunk_generator::ChunkGenerator;
+use crate::heightmap::Heightmap;
+use crate::random::random_state::RandomState;
+use crate::registry::mc_meta::McMetaRegistry;
+use crate::registry::Registry;
+
+#[test]
+fn base_height() {
+    let registry = Arc::new(McMetaRegistry::new(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../mcmeta"),
+        None,
+    ));
+
+    let settings = registry
+        .noise_generator_settings(&ident!("minecraft:overworld"))
+        .expect("should load overworld noise generator settings");
+
+    let random_state = Arc::new(RandomState::new(
+        &settings,
+        registry.clone(),
+        6646468147532173577,
+    ));
+    let generator =
+        ChunkGenerator::new(settings, plains(), random_state).expect("noise router should compile");
+
+    for (x, z) in [(0, 0), (-1234, 567), (4096, -4096), (100000, 100000)] {
+        let world_surface = generator.base_height(x, z, Heightmap::WorldSurfaceWg);
+        let ocean_floor = generator.base_height(x, z, Heightmap::OceanFloorWg);
+
+        assert!(world_surface >= ocean_floor);
+        assert!(world_surface >= generator.min_y() && world_surface <= generator.max_y());
+
+        let column = generator.base_column(x, z);
+        let top = (world_surface - generator.min_y()) as usize;
+        assert!(column[top..].iter().all(|s| s.is_air()));
+
+        let preliminary = generator.preliminary_surface_level(x, z);
+        assert!(preliminary == i32::MAX || preliminary >= generator.min_y());
+    }
+}
+
+#[test]
+fn spawn_position() {
+    let registry = Arc::new(McMetaRegistry::new(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../mcmeta"),
+        None,
+    ));
+
+    let settings = registry
+        .noise_generator_settings(&ident!("minecraft:overworld"))
+        .expect("should load overworld noise generator settings");
+
+    let random_state = Arc::new(RandomState::new(
+        &settings,
+        registry.clone(),
+        6646468147532173577,
+    ));
+    let generator =
+        ChunkGenerator::new(settings, plains(), random_state).expect("noise router should compile");
+
+    let spawn = generator.spawn_position();
+    assert!(spawn.y >= generator.settings().sea_level);
+    assert_eq!(
+        spawn.y,
+        generator.base_height(spawn.x, spawn.z, Heightmap::OceanFloorWg)
+    );
+}
+
+fn plains() -> BiomeSource {
+    BiomeSource::Fixed {
+        biome: ident!("minecraft:plains").to_string_ident(),
+    }
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+use valence_core::ident;
+
+use crate::biome::source::BiomeSource;
+use crate::chunk_generator::ChunkGenerator;
+use crate::random::random_state::RandomState;
+use crate::registry::mc_meta::McMetaRegistry;
+use crate::registry::Registry;
+
+fn overworld(biome_source: BiomeSource) -> ChunkGenerator {
+    let registry = Arc::new(McMetaRegistry::new(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../../mcmeta"),
+        None,
+    ));
+
+    let settings = registry
+        .noise_generator_settings(&ident!("minecraft:overworld"))
+        .expect("should load overworld noise generator settings");
+
+    let random_state = Arc::new(RandomState::new(
+        &settings,
+        registry.clone(),
+        6646468147532173577,
+    ));
+    ChunkGenerator::new(settings, biome_source, random_state).expect("noise router should compile")
+}
+
+#[test]
+fn locate_biome() {
+    let generator = overworld(BiomeSource::Fixed {
+        biome: ident!("minecraft:plains").to_string_ident(),
+    });
+    let origin = BlockPos::new(100, 64, -100);
+
+    let (pos, biome) = generator
+        .locate_biome(origin, 6400, 32, 64, |b| b.as_str() == "minecraft:plains")
+        .expect("plains should be everywhere");
+    assert_eq!(origin, pos);
+    assert_eq!("minecraft:plains", biome.as_str());
+
+    assert!(generator
+        .locate_biome(origin, 6400, 32, 64, |b| b.as_str() == "minecraft:desert")
+        .is_none());
+}
+
+#[test]
+fn locate_structure() {
+    let generator = overworld(BiomeSource::Fixed {
+        biome: ident!("minecraft:plains").to_string_ident(),
+    });
+    let origin = BlockPos::new(0, 64, 0);
+
+    let (pos, structure) = generator
+        .locate_structure(&[ident!("minecraft:villages")], origin, 100, |s| {
+            s.as_str() == "minecraft:village_plains"
+        })
+        .expect("villages should load")
+        .expect("plains villages should be found in plains");
+    assert_eq!("minecraft:village_plains", structure.as_str());
+
+    assert!(generator
+        .is_structure_chunk(&ident!("minecraft:villages"), pos.x >> 4, pos.z >> 4)
+        .expect("villages are placed on a grid"));
+
+    assert!(generator
+        .locate_structure(&[ident!("minecraft:villages")], origin, 100, |_| false)
+        .expect("villages should load")
+        .is_none());
+    assert!(generator
+        .locate_structure(&[ident!("minecraft:villages")], origin, 100, |s| {
+            s.as_str() == "minecraft:village_desert"
+        })
+        .expect("biome tags should load")
+        .is_none());
+}
@@ -1 +1,75 @@
+mod beardifier;
 mod biome_gen;
+#[cfg(feature = "embedded")]
+mod embedded;
+mod heightmap;
+mod locate;
+mod placement;
+mod reload;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::noise::deserialize::NoiseGeneratorSettings;
+
+/// A directory below the system's temp directory for files a test writes,
+/// e.g. packs, deleted with everything in it when dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    /// `name` should be unique among the tests, the directory is emptied if
+    /// it exists already.
+    pub(crate) fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("valence_worldgen_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("should create temp directory");
+        Self(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes `files`, paths relative to this directory and their contents,
+    /// creating the directories they are in.
+    pub(crate) fn write(&self, files: &[(&str, &str)]) {
+        for (path, contents) in files {
+            let path = self.0.join(path);
+            fs::create_dir_all(path.parent().unwrap()).expect("should create directory");
+            fs::write(path, contents).expect("should write file");
+        }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Noise generator settings for tests, sampling `test:final_density` and
+/// flat zeros for the rest of the router.
+pub(crate) const NOISE_SETTINGS: &str = r#"{
+  "noise": { "min_y": -64, "height": 384, "size_horizontal": 1, "size_vertical": 2 },
+  "default_block": { "Name": "minecraft:stone" },
+  "default_fluid": { "Name": "minecraft:water", "Properties": { "level": "0" } },
+  "noise_router": {
+    "barrier": 0, "continents": 0, "depth": 0, "erosion": 0,
+    "final_density": "test:final_density",
+    "fluid_level_floodedness": 0, "fluid_level_spread": 0,
+    "initial_density_without_jaggedness": 0, "lava": 0, "ridges": 0,
+    "temperature": 0, "vegetation": 0, "vein_gap": 0, "vein_ridged": 0, "vein_toggle": 0
+  },
+  "spawn_target": [],
+  "sea_level": 63,
+  "disable_mob_generation": false,
+  "aquifers_enabled": false,
+  "ore_veins_enabled": false,
+  "legacy_random_source": false
+}"#;
+
+/// [`NOISE_SETTINGS`] deserialized.
+pub(crate) fn noise_settings() -> NoiseGeneratorSettings {
+    serde_json::from_str(NOISE_SETTINGS).expect("should be valid noise generator settings")
+}
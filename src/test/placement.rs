@@ -0,0 +1,208 @@
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+use valence_core::ident;
+use valence_core::ident::Ident;
+
+use crate::biome::source::BiomeSource;
+use crate::chunk_generator::ChunkGenerator;
+use crate::density_function::deserialize::DensityFunctionTree;
+use crate::random::random_state::RandomState;
+use crate::registry::memory::MemoryRegistry;
+use crate::structure::placement::StructureSet;
+use crate::structure::Structure;
+use crate::test::noise_settings;
+
+const RINGS: &str = r#"{
+  "placement": {
+    "type": "minecraft:concentric_rings",
+    "salt": 0,
+    "distance": 32,
+    "spread": 3,
+    "count": 10,
+    "preferred_biomes": "minecraft:plains",
+    "locate_offset": [1, 2, 3]
+  },
+  "structures": [{ "structure": "test:stronghold", "weight": 1 }]
+}"#;
+
+const GRID: &str = r#"{
+  "placement": { "type": "minecraft:random_spread", "salt": 1, "spacing": 4, "separation": 1 },
+  "structures": [{ "structure": "test:stronghold", "weight": 1 }]
+}"#;
+
+const EXCLUDED: &str = r#"{
+  "placement": {
+    "type": "minecraft:random_spread",
+    "salt": 2,
+    "spacing": 2,
+    "separation": 1,
+    "exclusion_zone": { "other_set": "test:grid", "chunk_count": 1 }
+  },
+  "structures": [{ "structure": "test:stronghold", "weight": 1 }]
+}"#;
+
+/// Excludes itself through `test:excluded_back`.
+const CYCLE: &str = r#"{
+  "placement": {
+    "type": "minecraft:random_spread",
+    "salt": 3,
+    "spacing": 2,
+    "separation": 1,
+    "exclusion_zone": { "other_set": "test:excluded_back", "chunk_count": 1 }
+  },
+  "structures": [{ "structure": "test:stronghold", "weight": 1 }]
+}"#;
+
+const CYCLE_BACK: &str = r#"{
+  "placement": {
+    "type": "minecraft:random_spread",
+    "salt": 4,
+    "spacing": 1,
+    "separation": 0,
+    "exclusion_zone": { "other_set": "test:cycle", "chunk_count": 1 }
+  },
+  "structures": [{ "structure": "test:stronghold", "weight": 1 }]
+}"#;
+
+const STRONGHOLD: &str = r#"{ "type": "minecraft:stronghold", "biomes": "minecraft:plains" }"#;
+
+fn generator() -> ChunkGenerator {
+    let settings = Arc::new(noise_settings());
+    let set = |json| -> StructureSet {
+        serde_json::from_str(json).expect("should be a valid structure set")
+    };
+    let stronghold: Structure =
+        serde_json::from_str(STRONGHOLD).expect("should be a valid structure");
+    let registry = Arc::new(
+        MemoryRegistry::new()
+            .with_density_function(
+                &ident!("test:final_density"),
+                DensityFunctionTree::Constant(0.0),
+            )
+            .with(&ident!("test:rings"), set(RINGS))
+            .with(&ident!("test:grid"), set(GRID))
+            .with(&ident!("test:excluded"), set(EXCLUDED))
+            .with(&ident!("test:cycle"), set(CYCLE))
+            .with(&ident!("test:excluded_back"), set(CYCLE_BACK))
+            .with(&ident!("test:stronghold"), stronghold),
+    );
+
+    let random_state = Arc::new(RandomState::new(&settings, registry, 6646468147532173577));
+    ChunkGenerator::new(
+        settings,
+        BiomeSource::Fixed {
+            biome: ident!("minecraft:plains").to_string_ident(),
+        },
+        random_state,
+    )
+    .expect("generator should build")
+}
+
+#[test]
+fn concentric_rings() {
+    let generator = generator();
+    let rings = ident!("test:rings");
+
+    let positions = generator
+        .ring_positions(&rings)
+        .expect("rings should be placed");
+    assert_eq!(10, positions.len());
+
+    // 3 positions in the first ring, 6 in the second and the rest in the third,
+    // each moved by up to 112 blocks to a preferred biome
+    let distance = |&(x, z): &(i32, i32)| ((x * x + z * z) as f64).sqrt();
+    for (i, position) in positions.iter().enumerate() {
+        let ring = match i {
+            0..=2 => 0,
+            3..=8 => 1,
+            _ => 2,
+        };
+        let expected = (4 * 32 + 6 * 32 * ring) as f64;
+        let d = distance(position);
+        assert!(
+            (d - expected).abs() <= 40.0 + 8.0,
+            "{i}: {d} =?= {expected}"
+        );
+        assert!(generator
+            .is_structure_chunk(&rings, position.0, position.1)
+            .unwrap());
+    }
+
+    let (pos, structure) = generator
+        .locate_structure(&[rings], BlockPos::new(0, 64, 0), 0, |_| true)
+        .expect("structures should load")
+        .expect("a stronghold should be found");
+    assert_eq!("test:stronghold", structure.as_str());
+    let (x, z) = positions[..3]
+        .iter()
+        .copied()
+        .min_by_key(|&(x, z)| {
+            let (x, z) = (((x << 4) + 8) as i64, ((z << 4) + 8) as i64);
+            x * x + z * z
+        })
+        .unwrap();
+    assert_eq!(BlockPos::new((x << 4) + 1, 2, (z << 4) + 3), pos);
+
+    assert!(generator.ring_positions(&ident!("test:grid")).is_err());
+}
+
+#[test]
+fn exclusion_zone() {
+    let generator = generator();
+    let sets = |id: Ident<&str>| {
+        generator
+            .random_state()
+            .registry
+            .structure_set(&id)
+            .expect("structure set should load")
+    };
+    let (grid, excluded) = (sets(ident!("test:grid")), sets(ident!("test:excluded")));
+    let seed = generator.random_state().seed;
+
+    let mut rejected = 0;
+    for x in -16..16 {
+        for z in -16..16 {
+            let near_grid = (x - 1..=x + 1).any(|x| {
+                (z - 1..=z + 1).any(|z| grid.placement.is_placement_chunk(seed, x, z).unwrap())
+            });
+            let placed = excluded.placement.is_placement_chunk(seed, x, z).unwrap();
+            assert_eq!(
+                placed && !near_grid,
+                generator
+                    .is_structure_chunk(&ident!("test:excluded"), x, z)
+                    .unwrap(),
+                "{x} {z}"
+            );
+            rejected += (placed && near_grid) as i32;
+        }
+    }
+    assert!(rejected > 0);
+}
+
+#[test]
+fn exclusion_zone_cycle() {
+    let generator = generator();
+    let cycle = ident!("test:cycle");
+    let placement = &generator
+        .random_state()
+        .registry
+        .structure_set(&cycle)
+        .expect("structure set should load")
+        .placement;
+    let seed = generator.random_state().seed;
+
+    let (x, z) = (0..16)
+        .flat_map(|x| (0..16).map(move |z| (x, z)))
+        .find(|&(x, z)| {
+            placement.is_placement_chunk(seed, x, z).unwrap()
+                && placement.should_generate(seed, x, z)
+        })
+        .expect("test:cycle should be placed somewhere");
+    let error = generator
+        .is_structure_chunk(&cycle, x, z)
+        .expect_err("the cycle should be rejected");
+    assert!(error
+        .to_string()
+        .contains("test:cycle -> test:excluded_back -> test:cycle"));
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use valence_core::block_pos::BlockPos;
+use valence_core::ident;
+
+use crate::biome::source::BiomeSource;
+use crate::chunk_generator::ReloadableGenerator;
+use crate::density_function::deserialize::DensityFunctionTree;
+use crate::registry::mc_meta::McMetaRegistry;
+use crate::registry::Registry;
+use crate::test::{TempDir, NOISE_SETTINGS};
+
+fn write_final_density(root: &TempDir, json: &str) {
+    root.write(&[(
+        "data/test/worldgen/density_function/final_density.json",
+        json,
+    )]);
+}
+
+#[test]
+fn reload_keeps_snapshots() {
+    let root = TempDir::new("reload");
+    root.write(&[(
+        "data/test/worldgen/noise_settings/settings.json",
+        NOISE_SETTINGS,
+    )]);
+    write_final_density(&root, "0.5");
+
+    let generator = ReloadableGenerator::new(
+        ident!("test:settings").to_string_ident(),
+        BiomeSource::Fixed {
+            biome: ident!("minecraft:plains").to_string_ident(),
+        },
+        {
+            let root = root.path().to_str().unwrap().to_string();
+            move || Ok(Arc::new(McMetaRegistry::new(&root, None)) as Arc<dyn Registry>)
+        },
+        42,
+    )
+    .expect("generator should build");
+
+    let pos = BlockPos::new(0, 0, 0);
+    let old = generator.current();
+    assert_eq!(0.5, old.router().final_density.compute(pos));
+
+    write_final_density(&root, "0.25");
+    assert_eq!(0.5, generator.current().router().final_density.compute(pos));
+    generator.reload().expect("updated function should compile");
+    assert_eq!(
+        0.25,
+        generator.current().router().final_density.compute(pos)
+    );
+    assert_eq!(0.5, old.router().final_density.compute(pos));
+
+    write_final_density(&root, r#"{"type": "minecraft:abs"}"#);
+    assert!(generator.reload().is_err());
+    let current = generator.current();
+    assert_eq!(0.25, current.router().final_density.compute(pos));
+    match current
+        .random_state()
+        .registry
+        .density_function(&ident!("test:final_density"))
+        .as_deref()
+    {
+        Ok(DensityFunctionTree::Constant(v)) => assert_eq!(0.25, *v),
+        _ => panic!("the registry of the current generator should be unchanged"),
+    }
+}